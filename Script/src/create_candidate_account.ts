@@ -17,12 +17,13 @@ const electionInstructionLayout = borsh.struct([
     borsh.u8('variant'),
     borsh.str('first_name'),
     borsh.str('last_name'),
+    borsh.str('birth_date'),
     borsh.str('election_name'),
     borsh.str('seed')
     
 ])
 
-async function createCandidate(signer: web3.Keypair, programId: web3.PublicKey, connection: web3.Connection, firstName: string, lastName: string ){
+async function createCandidate(signer: web3.Keypair, programId: web3.PublicKey, connection: web3.Connection, firstName: string, lastName: string, birthDate: string, index: number ){
     let buffer = Buffer.alloc(1000)
     const first_name = firstName
     const last_name = lastName
@@ -33,6 +34,7 @@ async function createCandidate(signer: web3.Keypair, programId: web3.PublicKey,
             variant: 1,
             first_name: first_name,
             last_name: last_name,
+            birth_date: birthDate,
             election_name:election_name,
             seed:seed
         },
@@ -42,11 +44,6 @@ async function createCandidate(signer: web3.Keypair, programId: web3.PublicKey,
     buffer = buffer.slice(0, electionInstructionLayout.getSpan(buffer))
 
 
-    const [pda] = await web3.PublicKey.findProgramAddress(
-        [signer.publicKey.toBuffer(), Buffer.from(election_name), Buffer.from(first_name),Buffer.from(last_name)],
-        programId
-    )
-
     const[pda_candidate_list] = await web3.PublicKey.findProgramAddress(
         [programId.toBuffer(), Buffer.from(election_name),Buffer.from(seed)],
         programId
@@ -57,6 +54,19 @@ async function createCandidate(signer: web3.Keypair, programId: web3.PublicKey,
         programId
     )
 
+    // Il candidato e' derivato dall'elezione e dalla sua posizione nella lista candidati
+    const index_buffer = Buffer.alloc(4)
+    index_buffer.writeUInt32LE(index)
+    const [pda] = await web3.PublicKey.findProgramAddress(
+        [election_pda_account.toBuffer(), Buffer.from('candidate'), index_buffer],
+        programId
+    )
+
+    const[counters_pda_account] = await web3.PublicKey.findProgramAddress(
+        [programId.toBuffer(), Buffer.from(election_name), Buffer.from('counters')],
+        programId
+    )
+
     console.log("PDA is:", pda.toBase58())
 
     const transaction = new web3.Transaction()
@@ -70,6 +80,11 @@ async function createCandidate(signer: web3.Keypair, programId: web3.PublicKey,
                 isSigner: true,
                 isWritable: false
             },
+            {
+                pubkey: signer.publicKey,
+                isSigner: true,
+                isWritable: true
+            },
             {
                 pubkey: pda,
                 isSigner: false,
@@ -89,6 +104,11 @@ async function createCandidate(signer: web3.Keypair, programId: web3.PublicKey,
                 pubkey: web3.SystemProgram.programId,
                 isSigner: false,
                 isWritable: false
+            },
+            {
+                pubkey: counters_pda_account,
+                isSigner: false,
+                isWritable: true
             }
         ]
     })
@@ -104,8 +124,24 @@ function waitAirdropSol(secondi: number): Promise<void> {
     });
 }
 
+// L'autorita' dell'elezione e' chi la crea: creazione e candidati usano la stessa chiave del file .env
+function initializeSignerKeypair(): web3.Keypair {
+    if (!process.env.PRIVATE_KEY) {
+        console.log('Creating .env file')
+        const signer = web3.Keypair.generate()
+        fs.writeFileSync('.env', `PRIVATE_KEY=[${signer.secretKey.toString()}]`)
+        return signer
+    }
+
+    const secret = JSON.parse(process.env.PRIVATE_KEY ?? "") as number[]
+    const secretKey = Uint8Array.from(secret)
+    const keypairFromSecretKey = web3.Keypair.fromSecretKey(secretKey)
+    console.log('Signer public key:', keypairFromSecretKey.publicKey.toBase58())
+    return keypairFromSecretKey
+}
+
 async function main() {
-    const signer =  web3.Keypair.generate()
+    const signer = initializeSignerKeypair()
     
     const connection = new web3.Connection("http://127.0.0.1:8899")
     await airdropSolIfNeeded(signer, connection)
@@ -113,12 +149,12 @@ async function main() {
     await waitAirdropSol(15)
     
     const chainDemocracyProgramId = new web3.PublicKey('DEVqjbNXCGwT2rjLCVk6qUtVVtyCn2yLE88ChNkRLiWZ')          // ALDO
-    await createCandidate(signer, chainDemocracyProgramId, connection, 'Matteo', 'Salvini')
-    await createCandidate(signer, chainDemocracyProgramId, connection, 'Giuseppe', 'Conte')
-    await createCandidate(signer, chainDemocracyProgramId, connection, 'Luigi', 'Di Maio')
-    await createCandidate(signer, chainDemocracyProgramId, connection, 'Giorgia', 'Meloni')
-    await createCandidate(signer, chainDemocracyProgramId, connection, 'Silvio', 'Berlusconi')
-    await createCandidate(signer, chainDemocracyProgramId, connection, 'Matteo', 'Renzi')
+    await createCandidate(signer, chainDemocracyProgramId, connection, 'Matteo', 'Salvini', '1973-03-09', 0)
+    await createCandidate(signer, chainDemocracyProgramId, connection, 'Giuseppe', 'Conte', '1964-08-08', 1)
+    await createCandidate(signer, chainDemocracyProgramId, connection, 'Luigi', 'Di Maio', '1986-07-06', 2)
+    await createCandidate(signer, chainDemocracyProgramId, connection, 'Giorgia', 'Meloni', '1977-01-15', 3)
+    await createCandidate(signer, chainDemocracyProgramId, connection, 'Silvio', 'Berlusconi', '1936-09-29', 4)
+    await createCandidate(signer, chainDemocracyProgramId, connection, 'Matteo', 'Renzi', '1975-01-11', 5)
 }
 
 main().then(() => {
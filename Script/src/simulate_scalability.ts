@@ -2,6 +2,7 @@ import * as web3 from '@solana/web3.js';
 import * as borsh from '@project-serum/borsh';
 import * as dotenv from 'dotenv';
 import * as fs from 'fs'
+import { createHash } from 'crypto'
 
 dotenv.config();
 
@@ -29,16 +30,23 @@ const {
   
 
 const TOTAL_TRANSACTIONS = 5000; // Numero totale di transazioni da inviare
+const NUMBER_OF_SHARDS = 0; // Contatori distribuiti creati con AddVoteShards (0 = voti nell'account contatori)
+
+// Stesso calcolo di shard_index nel programma: primi 8 byte dello SHA-256 della tessera, little endian
+function shardIndex(electoralCardNumber: string): number {
+    const hash = createHash('sha256').update(electoralCardNumber).digest();
+    return Number(hash.readBigUInt64LE(0) % BigInt(NUMBER_OF_SHARDS));
+}
 
 const electionInstructionLayout = borsh.struct([
     borsh.u8('variant'),
     borsh.str('electoral_card_number'),
-    borsh.str('first_name'),
-    borsh.str('last_name'),
+    borsh.u32('candidate_index'),
     borsh.str('election_name'),
     borsh.str('seed'),
 ]);
 
+// Candidati in ordine di registrazione: la posizione e' l'indice usato nella scheda
 const names = [
     { firstName: "Matteo", lastName: "Salvini" },
     { firstName: "Giuseppe", lastName: "Conte" },
@@ -48,9 +56,8 @@ const names = [
     { firstName: "Matteo", lastName: "Renzi" }
 ];
 
-function selectRandomName() {
-    const randomIndex = Math.floor(Math.random() * names.length);
-    return names[randomIndex];
+function selectRandomCandidate() {
+    return Math.floor(Math.random() * names.length);
 }
 
 async function sendSingleVote(
@@ -60,22 +67,19 @@ async function sendSingleVote(
     index: number,
     pda_candidate_list: web3.PublicKey,
     pda_election: web3.PublicKey,
-    firstName: string,
-    lastName: string
+    candidateIndex: number
     ) {
 
     let buffer = Buffer.alloc(1000);
     const electoral_card_number = `FF${index}`;
-    const first_name = firstName;
-    const last_name = lastName;
+    const candidate_index = candidateIndex;
     const election_name = 'Elettorale1';
     const seed = 'candidate-list';
     electionInstructionLayout.encode(
         {
             variant: 2,
             electoral_card_number: electoral_card_number,
-            first_name: first_name,
-            last_name: last_name,
+            candidate_index: candidate_index,
             election_name: election_name,
             seed: seed
         },
@@ -101,6 +105,11 @@ async function sendSingleVote(
                 isSigner: true,
                 isWritable: false
             },
+            {
+                pubkey: signer.publicKey,
+                isSigner: true,
+                isWritable: true
+            },
             {
                 pubkey: pda,
                 isSigner: false,
@@ -109,12 +118,12 @@ async function sendSingleVote(
             {
                 pubkey: pda_candidate_list,
                 isSigner: false,
-                isWritable: true
+                isWritable: false
             },
             {
                 pubkey: pda_election,
                 isSigner: false,
-                isWritable: true
+                isWritable: false
             },
             {
                 pubkey: web3.SystemProgram.programId,
@@ -124,6 +133,25 @@ async function sendSingleVote(
         ]
     });
 
+    // La tessera deve essere iscritta nelle liste elettorali con la chiave del signer
+    const [pda_roll_entry] = await web3.PublicKey.findProgramAddress(
+        [programId.toBuffer(), Buffer.from(election_name), Buffer.from('roll'), Buffer.from(electoral_card_number)],
+        programId
+    )
+    instruction.keys.push({ pubkey: pda_roll_entry, isSigner: false, isWritable: false });
+
+    // Il voto scrive solo il contatore: quello distribuito del votante oppure l'account contatori
+    const [pda_counter] = NUMBER_OF_SHARDS > 0
+        ? await web3.PublicKey.findProgramAddress(
+            [programId.toBuffer(), Buffer.from(election_name), Buffer.from('shard'), Buffer.from([shardIndex(electoral_card_number)])],
+            programId
+        )
+        : await web3.PublicKey.findProgramAddress(
+            [programId.toBuffer(), Buffer.from(election_name), Buffer.from('counters')],
+            programId
+        )
+    instruction.keys.push({ pubkey: pda_counter, isSigner: false, isWritable: true });
+
     const transaction = new web3.Transaction();
     transaction.add(instruction);
     const tx = await web3.sendAndConfirmTransaction(connection, transaction, [signer]);
@@ -133,9 +161,9 @@ async function sendSingleVote(
 async function sendVoteConcurrent(signer: web3.Keypair, programId: web3.PublicKey, connection: web3.Connection, pda_candidate_list: web3.PublicKey, pda_election: web3.PublicKey) {
     const promises = [];
     for (let i = 0; i < TOTAL_TRANSACTIONS; i++) {
-        const randomName = selectRandomName();
+        const candidateIndex = selectRandomCandidate();
 
-        promises.push(sendSingleVote(signer, programId, connection, i, pda_candidate_list, pda_election, randomName.firstName, randomName.lastName));
+        promises.push(sendSingleVote(signer, programId, connection, i, pda_candidate_list, pda_election, candidateIndex));
     }
     await Promise.all(promises);
 }
@@ -1,6 +1,8 @@
 use borsh::BorshDeserialize;
 use chrono:: NaiveDateTime;
-use solana_program::program_error::ProgramError;
+use solana_program::{program_error::ProgramError, pubkey::Pubkey};
+
+use crate::state::{election_account_state::{VotingMode, TieBreakPolicy, Quorum, MajorityRule, RandomnessRound}, delegation_state::DelegationScope};
 
 pub enum ChainDemocracyInstruction {
     AddElectionAccount{
@@ -17,18 +19,164 @@ pub enum ChainDemocracyInstruction {
     AddCandidate {
         first_name: String,
         last_name: String,
+        birth_date: String,
         election_name: String,
         seed: String
     },
     AddVote {
         electoral_card_number: String,
-        candidate_first_name: String,
-        candidate_last_name: String,
+        candidate_index: u32,
         election_name: String,
         seed: String
     },
     CountingVotes {
         election_name: String,
+    },
+    AddRunoffElection {
+        first_round_name: String,
+        name: String,
+        start_date: NaiveDateTime,
+        end_date: NaiveDateTime
+    },
+    AddDistrict {
+        election_name: String,
+        district_name: String
+    },
+    AddDistrictVote {
+        electoral_card_number: String,
+        candidate_index: u32,
+        election_name: String,
+        seed: String,
+        district_name: String
+    },
+    CountingDistrictVotes {
+        election_name: String,
+        district_name: String
+    },
+    AggregateDistrictResults {
+        election_name: String
+    },
+    SetVotingMode {
+        election_name: String,
+        voting_mode: VotingMode
+    },
+    AddRollEntry {
+        election_name: String,
+        electoral_card_number: String,
+        weight: u64,
+        voter: Pubkey
+    },
+    SetTokenVoting {
+        election_name: String,
+        mint: Pubkey
+    },
+    AddTokenVote {
+        candidate_index: u32,
+        election_name: String,
+        seed: String
+    },
+    WithdrawTokens {
+        election_name: String
+    },
+    SetQuadraticVoting {
+        election_name: String,
+        voice_credits: u64
+    },
+    AddQuadraticVote {
+        electoral_card_number: String,
+        candidate_index: u32,
+        election_name: String,
+        seed: String,
+        votes: u64
+    },
+    SetElectionTopic {
+        election_name: String,
+        topic: String
+    },
+    AddDelegation {
+        election_name: String,
+        electoral_card_number: String,
+        delegate_card_number: String,
+        scope: DelegationScope
+    },
+    RevokeDelegation {
+        election_name: String,
+        electoral_card_number: String,
+        scope: DelegationScope
+    },
+    SetTieBreakPolicy {
+        election_name: String,
+        tie_break: TieBreakPolicy
+    },
+    SetTrustees {
+        election_name: String,
+        trustees: Vec<Pubkey>
+    },
+    CommitRandomness {
+        election_name: String,
+        round: RandomnessRound,
+        commitment: [u8; 32]
+    },
+    RevealRandomness {
+        election_name: String,
+        round: RandomnessRound,
+        secret: [u8; 32]
+    },
+    RecountVotes {
+        election_name: String
+    },
+    SetQuorumRules {
+        election_name: String,
+        quorum: Quorum,
+        majority_rule: MajorityRule
+    },
+    SetEmbargo {
+        election_name: String,
+        embargo: bool
+    },
+    ShowProvisionalResults {
+        election_name: String
+    },
+    CountingVotesPage {
+        election_name: String,
+        page_size: u32
+    },
+    AddVoteShards {
+        election_name: String,
+        number_of_shards: u8
+    },
+    MigrateAccount {
+        election_name: String
+    },
+    ArchiveElection {
+        election_name: String
+    },
+    CloseArchivedAccounts {
+        election_name: String
+    },
+    AddRelayedVote {
+        electoral_card_number: String,
+        candidate_index: u32,
+        election_name: String,
+        seed: String,
+        district_name: Option<String>,
+        nonce: u64
+    },
+    FinalizeRandomness {
+        election_name: String,
+        round: RandomnessRound
+    },
+    AddSealedVote {
+        electoral_card_number: String,
+        election_name: String,
+        district_name: Option<String>,
+        commitment: [u8; 32]
+    },
+    RevealSealedVote {
+        electoral_card_number: String,
+        election_name: String,
+        candidate_index: u32,
+        salt: [u8; 32]
     }
 }
 
@@ -36,6 +184,7 @@ pub enum ChainDemocracyInstruction {
 struct AddCandidatePayload {
     first_name: String,
     last_name: String,
+    birth_date: String,
     election_name: String,
     seed: String,
 }
@@ -49,8 +198,7 @@ struct  AddElectionAccountPayload{
 #[derive(BorshDeserialize)]
 struct  AddVotePayload{
     electoral_card_number: String,
-    candidate_first_name: String,
-    candidate_last_name: String,
+    candidate_index: u32,
     election_name: String,
     seed: String
 }
@@ -59,6 +207,216 @@ struct  AddVotePayload{
 struct  CountingVotesPayload{
     election_name: String,
 }
+
+#[derive(BorshDeserialize)]
+struct  AddRunoffElectionPayload{
+    first_round_name: String,
+    name: String,
+    start_date: String,
+    end_date: String
+}
+#[derive(BorshDeserialize)]
+struct  AddDistrictPayload{
+    election_name: String,
+    district_name: String
+}
+
+#[derive(BorshDeserialize)]
+struct  AddDistrictVotePayload{
+    electoral_card_number: String,
+    candidate_index: u32,
+    election_name: String,
+    seed: String,
+    district_name: String
+}
+
+#[derive(BorshDeserialize)]
+struct  CountingDistrictVotesPayload{
+    election_name: String,
+    district_name: String
+}
+
+#[derive(BorshDeserialize)]
+struct  AggregateDistrictResultsPayload{
+    election_name: String
+}
+
+#[derive(BorshDeserialize)]
+struct  SetVotingModePayload{
+    election_name: String,
+    voting_mode: VotingMode
+}
+
+#[derive(BorshDeserialize)]
+struct  AddRollEntryPayload{
+    election_name: String,
+    electoral_card_number: String,
+    weight: u64,
+    voter: Pubkey
+}
+
+#[derive(BorshDeserialize)]
+struct  SetTokenVotingPayload{
+    election_name: String,
+    mint: Pubkey
+}
+
+#[derive(BorshDeserialize)]
+struct  AddTokenVotePayload{
+    candidate_index: u32,
+    election_name: String,
+    seed: String
+}
+
+#[derive(BorshDeserialize)]
+struct  WithdrawTokensPayload{
+    election_name: String
+}
+
+#[derive(BorshDeserialize)]
+struct  SetQuadraticVotingPayload{
+    election_name: String,
+    voice_credits: u64
+}
+
+#[derive(BorshDeserialize)]
+struct  AddQuadraticVotePayload{
+    electoral_card_number: String,
+    candidate_index: u32,
+    election_name: String,
+    seed: String,
+    votes: u64
+}
+
+#[derive(BorshDeserialize)]
+struct  SetElectionTopicPayload{
+    election_name: String,
+    topic: String
+}
+
+#[derive(BorshDeserialize)]
+struct  AddDelegationPayload{
+    election_name: String,
+    electoral_card_number: String,
+    delegate_card_number: String,
+    scope: DelegationScope
+}
+
+#[derive(BorshDeserialize)]
+struct  RevokeDelegationPayload{
+    election_name: String,
+    electoral_card_number: String,
+    scope: DelegationScope
+}
+
+#[derive(BorshDeserialize)]
+struct  SetTieBreakPolicyPayload{
+    election_name: String,
+    tie_break: TieBreakPolicy
+}
+
+#[derive(BorshDeserialize)]
+struct  SetTrusteesPayload{
+    election_name: String,
+    trustees: Vec<Pubkey>
+}
+
+#[derive(BorshDeserialize)]
+struct  CommitRandomnessPayload{
+    election_name: String,
+    round: RandomnessRound,
+    commitment: [u8; 32]
+}
+
+#[derive(BorshDeserialize)]
+struct  RevealRandomnessPayload{
+    election_name: String,
+    round: RandomnessRound,
+    secret: [u8; 32]
+}
+
+#[derive(BorshDeserialize)]
+struct  RecountVotesPayload{
+    election_name: String
+}
+
+#[derive(BorshDeserialize)]
+struct  SetQuorumRulesPayload{
+    election_name: String,
+    quorum: Quorum,
+    majority_rule: MajorityRule
+}
+
+#[derive(BorshDeserialize)]
+struct  SetEmbargoPayload{
+    election_name: String,
+    embargo: bool
+}
+
+#[derive(BorshDeserialize)]
+struct  ShowProvisionalResultsPayload{
+    election_name: String
+}
+
+#[derive(BorshDeserialize)]
+struct  CountingVotesPagePayload{
+    election_name: String,
+    page_size: u32
+}
+
+#[derive(BorshDeserialize)]
+struct  AddVoteShardsPayload{
+    election_name: String,
+    number_of_shards: u8
+}
+
+#[derive(BorshDeserialize)]
+struct  MigrateAccountPayload{
+    election_name: String
+}
+
+#[derive(BorshDeserialize)]
+struct  ArchiveElectionPayload{
+    election_name: String
+}
+
+#[derive(BorshDeserialize)]
+struct  CloseArchivedAccountsPayload{
+    election_name: String
+}
+
+#[derive(BorshDeserialize)]
+struct  FinalizeRandomnessPayload{
+    election_name: String,
+    round: RandomnessRound
+}
+
+#[derive(BorshDeserialize)]
+struct  AddSealedVotePayload{
+    electoral_card_number: String,
+    election_name: String,
+    district_name: Option<String>,
+    commitment: [u8; 32]
+}
+
+#[derive(BorshDeserialize)]
+struct  RevealSealedVotePayload{
+    electoral_card_number: String,
+    election_name: String,
+    candidate_index: u32,
+    salt: [u8; 32]
+}
+
+#[derive(BorshDeserialize)]
+struct  AddRelayedVotePayload{
+    electoral_card_number: String,
+    candidate_index: u32,
+    election_name: String,
+    seed: String,
+    district_name: Option<String>,
+    nonce: u64
+}
+
 impl ChainDemocracyInstruction {
 
     pub fn unpack(input: &[u8]) -> Result<Self,ProgramError> {
@@ -79,6 +437,7 @@ impl ChainDemocracyInstruction {
                 Self::AddCandidate{
                     first_name: payload.first_name,
                     last_name: payload.last_name,
+                    birth_date: payload.birth_date,
                     election_name: payload.election_name,
                     seed: payload.seed
                 }
@@ -87,8 +446,7 @@ impl ChainDemocracyInstruction {
                 let payload = AddVotePayload::try_from_slice(rest).unwrap();
                 Self::AddVote { 
                     electoral_card_number: payload.electoral_card_number,
-                    candidate_first_name: payload.candidate_first_name,
-                    candidate_last_name: payload.candidate_last_name,
+                    candidate_index: payload.candidate_index,
                     election_name: payload.election_name,
                     seed: payload.seed
                  } 
@@ -97,6 +455,194 @@ impl ChainDemocracyInstruction {
                 let payload = CountingVotesPayload::try_from_slice(rest).unwrap();
                 Self::CountingVotes {election_name: payload.election_name}
             }
+            4 => {
+                let payload = AddRunoffElectionPayload::try_from_slice(rest).map_err(|_| ProgramError::InvalidInstructionData)?;
+                let parsed_start_date = NaiveDateTime::parse_from_str(&payload.start_date, "%Y-%m-%dT%H:%M:%S").map_err(|_| ProgramError::InvalidInstructionData)?;
+
+                let parsed_end_date = NaiveDateTime::parse_from_str(&payload.end_date, "%Y-%m-%dT%H:%M:%S").map_err(|_| ProgramError::InvalidInstructionData)?;
+
+                Self::AddRunoffElection {
+                    first_round_name: payload.first_round_name,
+                    name: payload.name,
+                    start_date: parsed_start_date,
+                    end_date: parsed_end_date
+                }
+            }
+            5 => {
+                let payload = AddDistrictPayload::try_from_slice(rest).map_err(|_| ProgramError::InvalidInstructionData)?;
+                Self::AddDistrict { election_name: payload.election_name, district_name: payload.district_name }
+            }
+            6 => {
+                let payload = AddDistrictVotePayload::try_from_slice(rest).map_err(|_| ProgramError::InvalidInstructionData)?;
+                Self::AddDistrictVote {
+                    electoral_card_number: payload.electoral_card_number,
+                    candidate_index: payload.candidate_index,
+                    election_name: payload.election_name,
+                    seed: payload.seed,
+                    district_name: payload.district_name
+                }
+            }
+            7 => {
+                let payload = CountingDistrictVotesPayload::try_from_slice(rest).map_err(|_| ProgramError::InvalidInstructionData)?;
+                Self::CountingDistrictVotes { election_name: payload.election_name, district_name: payload.district_name }
+            }
+            8 => {
+                let payload = AggregateDistrictResultsPayload::try_from_slice(rest).map_err(|_| ProgramError::InvalidInstructionData)?;
+                Self::AggregateDistrictResults { election_name: payload.election_name }
+            }
+            9 => {
+                let payload = SetVotingModePayload::try_from_slice(rest).map_err(|_| ProgramError::InvalidInstructionData)?;
+                Self::SetVotingMode { election_name: payload.election_name, voting_mode: payload.voting_mode }
+            }
+            10 => {
+                let payload = AddRollEntryPayload::try_from_slice(rest).map_err(|_| ProgramError::InvalidInstructionData)?;
+                Self::AddRollEntry {
+                    election_name: payload.election_name,
+                    electoral_card_number: payload.electoral_card_number,
+                    weight: payload.weight,
+                    voter: payload.voter
+                }
+            }
+            11 => {
+                let payload = SetTokenVotingPayload::try_from_slice(rest).map_err(|_| ProgramError::InvalidInstructionData)?;
+                Self::SetTokenVoting { election_name: payload.election_name, mint: payload.mint }
+            }
+            12 => {
+                let payload = AddTokenVotePayload::try_from_slice(rest).map_err(|_| ProgramError::InvalidInstructionData)?;
+                Self::AddTokenVote {
+                    candidate_index: payload.candidate_index,
+                    election_name: payload.election_name,
+                    seed: payload.seed
+                }
+            }
+            13 => {
+                let payload = WithdrawTokensPayload::try_from_slice(rest).map_err(|_| ProgramError::InvalidInstructionData)?;
+                Self::WithdrawTokens { election_name: payload.election_name }
+            }
+            14 => {
+                let payload = SetQuadraticVotingPayload::try_from_slice(rest).map_err(|_| ProgramError::InvalidInstructionData)?;
+                Self::SetQuadraticVoting { election_name: payload.election_name, voice_credits: payload.voice_credits }
+            }
+            15 => {
+                let payload = AddQuadraticVotePayload::try_from_slice(rest).map_err(|_| ProgramError::InvalidInstructionData)?;
+                Self::AddQuadraticVote {
+                    electoral_card_number: payload.electoral_card_number,
+                    candidate_index: payload.candidate_index,
+                    election_name: payload.election_name,
+                    seed: payload.seed,
+                    votes: payload.votes
+                }
+            }
+            16 => {
+                let payload = SetElectionTopicPayload::try_from_slice(rest).map_err(|_| ProgramError::InvalidInstructionData)?;
+                Self::SetElectionTopic { election_name: payload.election_name, topic: payload.topic }
+            }
+            17 => {
+                let payload = AddDelegationPayload::try_from_slice(rest).map_err(|_| ProgramError::InvalidInstructionData)?;
+                Self::AddDelegation {
+                    election_name: payload.election_name,
+                    electoral_card_number: payload.electoral_card_number,
+                    delegate_card_number: payload.delegate_card_number,
+                    scope: payload.scope
+                }
+            }
+            18 => {
+                let payload = RevokeDelegationPayload::try_from_slice(rest).map_err(|_| ProgramError::InvalidInstructionData)?;
+                Self::RevokeDelegation {
+                    election_name: payload.election_name,
+                    electoral_card_number: payload.electoral_card_number,
+                    scope: payload.scope
+                }
+            }
+            19 => {
+                let payload = SetTieBreakPolicyPayload::try_from_slice(rest).map_err(|_| ProgramError::InvalidInstructionData)?;
+                Self::SetTieBreakPolicy { election_name: payload.election_name, tie_break: payload.tie_break }
+            }
+            20 => {
+                let payload = SetTrusteesPayload::try_from_slice(rest).map_err(|_| ProgramError::InvalidInstructionData)?;
+                Self::SetTrustees { election_name: payload.election_name, trustees: payload.trustees }
+            }
+            21 => {
+                let payload = CommitRandomnessPayload::try_from_slice(rest).map_err(|_| ProgramError::InvalidInstructionData)?;
+                Self::CommitRandomness { election_name: payload.election_name, round: payload.round, commitment: payload.commitment }
+            }
+            22 => {
+                let payload = RevealRandomnessPayload::try_from_slice(rest).map_err(|_| ProgramError::InvalidInstructionData)?;
+                Self::RevealRandomness { election_name: payload.election_name, round: payload.round, secret: payload.secret }
+            }
+            23 => {
+                let payload = RecountVotesPayload::try_from_slice(rest).map_err(|_| ProgramError::InvalidInstructionData)?;
+                Self::RecountVotes { election_name: payload.election_name }
+            }
+            24 => {
+                let payload = SetQuorumRulesPayload::try_from_slice(rest).map_err(|_| ProgramError::InvalidInstructionData)?;
+                Self::SetQuorumRules {
+                    election_name: payload.election_name,
+                    quorum: payload.quorum,
+                    majority_rule: payload.majority_rule
+                }
+            }
+            25 => {
+                let payload = SetEmbargoPayload::try_from_slice(rest).map_err(|_| ProgramError::InvalidInstructionData)?;
+                Self::SetEmbargo { election_name: payload.election_name, embargo: payload.embargo }
+            }
+            26 => {
+                let payload = ShowProvisionalResultsPayload::try_from_slice(rest).map_err(|_| ProgramError::InvalidInstructionData)?;
+                Self::ShowProvisionalResults { election_name: payload.election_name }
+            }
+            27 => {
+                let payload = CountingVotesPagePayload::try_from_slice(rest).map_err(|_| ProgramError::InvalidInstructionData)?;
+                Self::CountingVotesPage { election_name: payload.election_name, page_size: payload.page_size }
+            }
+            28 => {
+                let payload = AddVoteShardsPayload::try_from_slice(rest).map_err(|_| ProgramError::InvalidInstructionData)?;
+                Self::AddVoteShards { election_name: payload.election_name, number_of_shards: payload.number_of_shards }
+            }
+            29 => {
+                let payload = MigrateAccountPayload::try_from_slice(rest).map_err(|_| ProgramError::InvalidInstructionData)?;
+                Self::MigrateAccount { election_name: payload.election_name }
+            }
+            30 => {
+                let payload = ArchiveElectionPayload::try_from_slice(rest).map_err(|_| ProgramError::InvalidInstructionData)?;
+                Self::ArchiveElection { election_name: payload.election_name }
+            }
+            31 => {
+                let payload = CloseArchivedAccountsPayload::try_from_slice(rest).map_err(|_| ProgramError::InvalidInstructionData)?;
+                Self::CloseArchivedAccounts { election_name: payload.election_name }
+            }
+            32 => {
+                let payload = AddRelayedVotePayload::try_from_slice(rest).map_err(|_| ProgramError::InvalidInstructionData)?;
+                Self::AddRelayedVote {
+                    electoral_card_number: payload.electoral_card_number,
+                    candidate_index: payload.candidate_index,
+                    election_name: payload.election_name,
+                    seed: payload.seed,
+                    district_name: payload.district_name,
+                    nonce: payload.nonce
+                }
+            }
+            33 => {
+                let payload = FinalizeRandomnessPayload::try_from_slice(rest).map_err(|_| ProgramError::InvalidInstructionData)?;
+                Self::FinalizeRandomness { election_name: payload.election_name, round: payload.round }
+            }
+            34 => {
+                let payload = AddSealedVotePayload::try_from_slice(rest).map_err(|_| ProgramError::InvalidInstructionData)?;
+                Self::AddSealedVote {
+                    electoral_card_number: payload.electoral_card_number,
+                    election_name: payload.election_name,
+                    district_name: payload.district_name,
+                    commitment: payload.commitment
+                }
+            }
+            35 => {
+                let payload = RevealSealedVotePayload::try_from_slice(rest).map_err(|_| ProgramError::InvalidInstructionData)?;
+                Self::RevealSealedVote {
+                    electoral_card_number: payload.electoral_card_number,
+                    election_name: payload.election_name,
+                    candidate_index: payload.candidate_index,
+                    salt: payload.salt
+                }
+            }
             _=> return Err(ProgramError::InvalidInstructionData)
         })
 
@@ -9,7 +9,7 @@ use solana_program::{
 pub mod instruction;
 use instruction::ChainDemocracyInstruction;
 pub mod pda_management;
-use pda_management::{candidate_manager_account, election_manager_account, candidate_list_manager_account, voter_manager_accout::add_voter_account_and_vote, result_manager_account::counting_votes};
+use pda_management::{candidate_manager_account, election_manager_account, candidate_list_manager_account, district_manager_account, roll_manager_account, delegation_manager_account, randomness_manager_account, shard_manager_account, migration_manager_account, archive_manager_account, voter_manager_accout::{add_voter_account_and_vote, add_relayed_vote, add_sealed_vote, reveal_sealed_vote, add_token_voter_and_vote, withdraw_locked_tokens, add_quadratic_vote}, result_manager_account::{counting_votes, counting_votes_page}};
 pub mod state;
 pub mod utilities;
 
@@ -27,20 +27,148 @@ pub fn process_instruction (
     match instruction {
         //CREA ACCOUNT ELEZIONE, LISTA CANDIDATI E RISULTATI
         ChainDemocracyInstruction::AddElectionAccount { name, start_date, end_date } => {
-           let _ = election_manager_account::add_election_account(program_id, accounts, name, start_date, end_date);
+            election_manager_account::add_election_account(program_id, accounts, name, start_date, end_date)?;
         }
         //CREA ACCOUNT CANDIDATO
-        ChainDemocracyInstruction::AddCandidate { first_name, last_name, election_name, seed } => {
-            let _ = candidate_manager_account::add_candidate(program_id, accounts, first_name, last_name, election_name, seed);
+        ChainDemocracyInstruction::AddCandidate { first_name, last_name, birth_date, election_name, seed } => {
+            candidate_manager_account::add_candidate(program_id, accounts, first_name, last_name, birth_date, election_name, seed)?;
         }
         //CREA ACCOUNT VOTANTE E REGISTRA IL VOTO NELL'ACCOUNT ELEZIONE 
-        ChainDemocracyInstruction::AddVote { electoral_card_number,candidate_first_name, candidate_last_name ,election_name,seed} => {
-            let _ = add_voter_account_and_vote(program_id, accounts, electoral_card_number, candidate_first_name, candidate_last_name, election_name, seed);  
+        ChainDemocracyInstruction::AddVote { electoral_card_number,candidate_index ,election_name,seed} => {
+            add_voter_account_and_vote(program_id, accounts, electoral_card_number, candidate_index, election_name, seed, None)?;
         }
-        //POPOLA L'ACCOUNT RISULTATI CON I RISULTATI DEI VOTI 
+        //SCRUTINIO DEFINITIVO: POPOLA L'ACCOUNT RISULTATI E CHIUDE L'ELEZIONE
         ChainDemocracyInstruction::CountingVotes { election_name } => {
             msg!("Risultati delle {}",election_name);
-            let _ = counting_votes(accounts);
+            counting_votes(program_id, accounts, election_name)?;
+        }
+        //CREA IL BALLOTTAGGIO CON I DUE FINALISTI DEL PRIMO TURNO
+        ChainDemocracyInstruction::AddRunoffElection { first_round_name, name, start_date, end_date } => {
+            election_manager_account::add_runoff_election(program_id, accounts, first_round_name, name, start_date, end_date)?;
+        }
+        //CREA UNA CIRCOSCRIZIONE E IL SUO ACCOUNT RISULTATI
+        ChainDemocracyInstruction::AddDistrict { election_name, district_name } => {
+            district_manager_account::add_district(program_id, accounts, election_name, district_name)?;
+        }
+        //CREA ACCOUNT VOTANTE E REGISTRA IL VOTO NELL'ELEZIONE E NELLA CIRCOSCRIZIONE
+        ChainDemocracyInstruction::AddDistrictVote { electoral_card_number, candidate_index, election_name, seed, district_name } => {
+            add_voter_account_and_vote(program_id, accounts, electoral_card_number, candidate_index, election_name, seed, Some(district_name))?;
+        }
+        //POPOLA L'ACCOUNT RISULTATI DELLA CIRCOSCRIZIONE
+        ChainDemocracyInstruction::CountingDistrictVotes { election_name, district_name } => {
+            district_manager_account::counting_district_votes(program_id, accounts, election_name, district_name)?;
+        }
+        //AGGREGA I RISULTATI DELLE CIRCOSCRIZIONI NEL RISULTATO NAZIONALE
+        ChainDemocracyInstruction::AggregateDistrictResults { election_name } => {
+            district_manager_account::aggregate_district_results(program_id, accounts, election_name)?;
+        }
+        //IMPOSTA LA MODALITA' DI VOTO DELL'ELEZIONE
+        ChainDemocracyInstruction::SetVotingMode { election_name, voting_mode } => {
+            election_manager_account::set_voting_mode(program_id, accounts, election_name, voting_mode)?;
+        }
+        //ISCRIVE UN VOTANTE NELLE LISTE ELETTORALI
+        ChainDemocracyInstruction::AddRollEntry { election_name, electoral_card_number, weight, voter } => {
+            roll_manager_account::add_roll_entry(program_id, accounts, election_name, electoral_card_number, weight, voter)?;
+        }
+        //ABILITA IL VOTO PONDERATO SUL SALDO DI UN TOKEN SPL
+        ChainDemocracyInstruction::SetTokenVoting { election_name, mint } => {
+            election_manager_account::set_token_voting(program_id, accounts, election_name, mint)?;
+        }
+        //REGISTRA IL VOTO BLOCCANDO I TOKEN DEL VOTANTE
+        ChainDemocracyInstruction::AddTokenVote { candidate_index, election_name, seed } => {
+            add_token_voter_and_vote(program_id, accounts, candidate_index, election_name, seed)?;
+        }
+        //RESTITUISCE I TOKEN BLOCCATI DOPO LA CHIUSURA
+        ChainDemocracyInstruction::WithdrawTokens { election_name } => {
+            withdraw_locked_tokens(program_id, accounts, election_name)?;
+        }
+        //ABILITA IL VOTO QUADRATICO CON UN BUDGET DI CREDITI
+        ChainDemocracyInstruction::SetQuadraticVoting { election_name, voice_credits } => {
+            election_manager_account::set_quadratic_voting(program_id, accounts, election_name, voice_credits)?;
+        }
+        //ASSEGNA VOTI QUADRATICI A UN CANDIDATO
+        ChainDemocracyInstruction::AddQuadraticVote { electoral_card_number, candidate_index, election_name, seed, votes } => {
+            add_quadratic_vote(program_id, accounts, electoral_card_number, candidate_index, election_name, seed, votes)?;
+        }
+        //IMPOSTA IL TEMA DELL'ELEZIONE
+        ChainDemocracyInstruction::SetElectionTopic { election_name, topic } => {
+            election_manager_account::set_election_topic(program_id, accounts, election_name, topic)?;
+        }
+        //DELEGA IL VOTO A UN ALTRO VOTANTE
+        ChainDemocracyInstruction::AddDelegation { election_name, electoral_card_number, delegate_card_number, scope } => {
+            delegation_manager_account::add_delegation(program_id, accounts, election_name, electoral_card_number, delegate_card_number, scope)?;
+        }
+        //REVOCA UNA DELEGA
+        ChainDemocracyInstruction::RevokeDelegation { election_name, electoral_card_number, scope } => {
+            delegation_manager_account::revoke_delegation(program_id, accounts, election_name, electoral_card_number, scope)?;
+        }
+        //IMPOSTA LA REGOLA DI SPAREGGIO
+        ChainDemocracyInstruction::SetTieBreakPolicy { election_name, tie_break } => {
+            election_manager_account::set_tie_break_policy(program_id, accounts, election_name, tie_break)?;
+        }
+        //NOMINA I GARANTI DEL SEME CASUALE
+        ChainDemocracyInstruction::SetTrustees { election_name, trustees } => {
+            randomness_manager_account::set_trustees(program_id, accounts, election_name, trustees)?;
+        }
+        //IMPEGNA L'HASH DEL SEGRETO DI UN GARANTE
+        ChainDemocracyInstruction::CommitRandomness { election_name, round, commitment } => {
+            randomness_manager_account::commit_randomness(program_id, accounts, election_name, round, commitment)?;
+        }
+        //RIVELA IL SEGRETO DI UN GARANTE E, CON L'ULTIMO, CALCOLA IL SEME
+        ChainDemocracyInstruction::RevealRandomness { election_name, round, secret } => {
+            randomness_manager_account::reveal_randomness(program_id, accounts, election_name, round, secret)?;
+        }
+        //AUTORIZZA IL RICONTEGGIO DI UN'ELEZIONE SCRUTINATA
+        ChainDemocracyInstruction::RecountVotes { election_name } => {
+            election_manager_account::recount_votes(program_id, accounts, election_name)?;
+        }
+        //IMPOSTA QUORUM E MAGGIORANZA RICHIESTA
+        ChainDemocracyInstruction::SetQuorumRules { election_name, quorum, majority_rule } => {
+            election_manager_account::set_quorum_rules(program_id, accounts, election_name, quorum, majority_rule)?;
+        }
+        //ATTIVA L'EMBARGO SUI CONTEGGI PROVVISORI
+        ChainDemocracyInstruction::SetEmbargo { election_name, embargo } => {
+            election_manager_account::set_embargo(program_id, accounts, election_name, embargo)?;
+        }
+        //RESTITUISCE AFFLUENZA E, SE CONSENTITO, CONTEGGI PROVVISORI
+        ChainDemocracyInstruction::ShowProvisionalResults { election_name } => {
+            election_manager_account::show_provisional_results(program_id, accounts, election_name)?;
+        }
+        //SCRUTINIO A PAGINE PER ELEZIONI CON MOLTI CANDIDATI
+        ChainDemocracyInstruction::CountingVotesPage { election_name, page_size } => {
+            counting_votes_page(program_id, accounts, election_name, page_size)?;
+        }
+        //CREA I CONTATORI DISTRIBUITI DEI VOTI
+        ChainDemocracyInstruction::AddVoteShards { election_name, number_of_shards } => {
+            shard_manager_account::add_vote_shards(program_id, accounts, election_name, number_of_shards)?;
+        }
+        //AGGIORNA GLI ACCOUNT DI UN'ELEZIONE AL LAYOUT CORRENTE
+        ChainDemocracyInstruction::MigrateAccount { election_name } => {
+            migration_manager_account::migrate_accounts(program_id, accounts, election_name)?;
+        }
+        //ARCHIVIA UN'ELEZIONE SCRUTINATA DOPO IL PERIODO DI CONSERVAZIONE
+        ChainDemocracyInstruction::ArchiveElection { election_name } => {
+            archive_manager_account::archive_election(program_id, accounts, election_name)?;
+        }
+        //CHIUDE GLI ACCOUNT DI VOTANTI E CANDIDATI E RESTITUISCE IL RENT
+        ChainDemocracyInstruction::CloseArchivedAccounts { election_name } => {
+            archive_manager_account::close_archived_accounts(program_id, accounts, election_name)?;
+        }
+        //REGISTRA UNA SCHEDA FIRMATA DAL VOTANTE E INOLTRATA DA UN RELAYER
+        ChainDemocracyInstruction::AddRelayedVote { electoral_card_number, candidate_index, election_name, seed, district_name, nonce } => {
+            add_relayed_vote(program_id, accounts, electoral_card_number, candidate_index, election_name, seed, district_name, nonce)?;
+        }
+        //CHIUDE UN'ESTRAZIONE CASUALE SE UN GARANTE NON HA RIVELATO ENTRO IL TERMINE
+        ChainDemocracyInstruction::FinalizeRandomness { election_name, round } => {
+            randomness_manager_account::finalize_randomness(program_id, accounts, election_name, round)?;
+        }
+        //REGISTRA UNA SCHEDA SIGILLATA SOTTO EMBARGO: SOLO AFFLUENZA
+        ChainDemocracyInstruction::AddSealedVote { electoral_card_number, election_name, district_name, commitment } => {
+            add_sealed_vote(program_id, accounts, electoral_card_number, election_name, district_name, commitment)?;
+        }
+        //RIVELA UNA SCHEDA SIGILLATA DOPO LA CHIUSURA E LA ASSEGNA AL CANDIDATO
+        ChainDemocracyInstruction::RevealSealedVote { electoral_card_number, election_name, candidate_index, salt } => {
+            reveal_sealed_vote(program_id, accounts, electoral_card_number, election_name, candidate_index, salt)?;
         }
         _=> return  Err(ProgramError::InvalidAccountData)
     }
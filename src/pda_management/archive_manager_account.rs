@@ -0,0 +1,164 @@
+use solana_program::{
+    entrypoint::ProgramResult,
+    pubkey::Pubkey,
+    msg,
+    account_info::{next_account_info, AccountInfo},
+    program_error::ProgramError
+};
+
+use crate::{
+    state::{
+        election_account_state::{ElectionAccountState, ElectionStatus},
+        result_state::ResultState,
+        candidate_state::CandidateState,
+        voter_account_state::VoterAccountState
+    },
+    utilities::{
+        election_account_utilities::is_retention_elapsed,
+        account_size_utilities::close_account,
+        account_state_utilities::{load_account, save_account, is_account_of}
+    }
+};
+
+//PERIODO DI CONSERVAZIONE DOPO LA FINE DELL'ELEZIONE PRIMA DELL'ARCHIVIAZIONE (30 GIORNI)
+pub const ARCHIVE_RETENTION_SECONDS: i64 = 30 * 24 * 60 * 60;
+
+//Archiviazione: dopo lo scrutinio definitivo e il periodo di conservazione l'autorita' archivia l'elezione.
+//Elezione, lista candidati e risultati restano come registro; gli account di votanti e candidati
+//possono essere chiusi e il loro rent va al destinatario indicato dall'autorita'.
+
+//ARCHIVIA UN'ELEZIONE SCRUTINATA: DA QUI IL RISULTATO NON PUO' PIU' ESSERE RICONTATO
+pub fn archive_election(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    election_name: String
+) -> ProgramResult {
+
+    let account_info_iter = &mut accounts.iter();
+
+    let initializer = next_account_info(account_info_iter)?;
+    let election_pda_account = next_account_info(account_info_iter)?;
+    let result_pda_account = next_account_info(account_info_iter)?;
+
+    let (election_pda, _election_bump_seed) = Pubkey::find_program_address(
+        &[program_id.as_ref(), election_name.as_bytes()],
+        program_id
+    );
+    let (result_pda, _result_bump_seed) = Pubkey::find_program_address(
+        &[program_id.as_ref(), election_name.as_bytes(), "result".as_bytes()],
+        program_id
+    );
+    if election_pda != *election_pda_account.key || result_pda != *result_pda_account.key {
+        msg!("Invalid seeds for PDA");
+        return Err(ProgramError::InvalidSeeds)
+    }
+    if election_pda_account.owner != program_id || result_pda_account.owner != program_id {
+        return Err(ProgramError::IllegalOwner)
+    }
+
+    let mut election_data = load_account::<ElectionAccountState>(election_pda_account)?;
+    let result_data = load_account::<ResultState>(result_pda_account)?;
+
+    if !initializer.is_signer || election_data.authority != *initializer.key {
+        msg!("Only the election authority can archive the election");
+        return Err(ProgramError::MissingRequiredSignature)
+    }
+    if election_data.status != ElectionStatus::Tallied || !result_data.is_final {
+        msg!("Election {} has no final tally", election_name);
+        return Err(ProgramError::InvalidAccountData)
+    }
+    if !is_retention_elapsed(&election_data.end_date, ARCHIVE_RETENTION_SECONDS)? {
+        msg!("Election {} is still in its retention period", election_name);
+        return Err(ProgramError::InvalidAccountData)
+    }
+
+    election_data.status = ElectionStatus::Archived;
+    msg!("Elezione {} archiviata", election_name);
+    save_account(&election_data, election_pda_account)?;
+
+    Ok(())
+}
+
+//CHIUDE GLI ACCOUNT DI VOTANTI E CANDIDATI DI UN'ELEZIONE ARCHIVIATA
+//Gli account da chiudere seguono il destinatario; un votante con token e' seguito dal wallet usato nei seed
+pub fn close_archived_accounts(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    election_name: String
+) -> ProgramResult {
+
+    let account_info_iter = &mut accounts.iter();
+
+    let initializer = next_account_info(account_info_iter)?;
+    let election_pda_account = next_account_info(account_info_iter)?;
+    let recipient = next_account_info(account_info_iter)?;
+
+    let (election_pda, _election_bump_seed) = Pubkey::find_program_address(
+        &[program_id.as_ref(), election_name.as_bytes()],
+        program_id
+    );
+    if election_pda != *election_pda_account.key {
+        msg!("Invalid seeds for PDA");
+        return Err(ProgramError::InvalidSeeds)
+    }
+    if election_pda_account.owner != program_id {
+        return Err(ProgramError::IllegalOwner)
+    }
+
+    let election_data = load_account::<ElectionAccountState>(election_pda_account)?;
+
+    if !initializer.is_signer || election_data.authority != *initializer.key {
+        msg!("Only the election authority can close archived accounts");
+        return Err(ProgramError::MissingRequiredSignature)
+    }
+    if election_data.status != ElectionStatus::Archived {
+        msg!("Election {} has not been archived", election_name);
+        return Err(ProgramError::InvalidAccountData)
+    }
+
+    let mut closed_accounts: u32 = 0;
+    let mut reclaimed_lamports: u64 = 0;
+
+    while let Ok(pda_account) = next_account_info(account_info_iter) {
+        if pda_account.owner != program_id {
+            return Err(ProgramError::IllegalOwner)
+        }
+
+        if is_account_of::<CandidateState>(pda_account) {
+            //I CANDIDATI DI UN BALLOTTAGGIO APPARTENGONO AL PRIMO TURNO E SI CHIUDONO DA LI'
+            if election_data.round > 1 || !election_data.candidates.contains(pda_account.key) {
+                msg!("Candidate {} does not belong to {}", pda_account.key, election_name);
+                return Err(ProgramError::InvalidArgument)
+            }
+        } else {
+            let voter_data = load_account::<VoterAccountState>(pda_account)?;
+
+            //I SEED DEL VOTANTE SONO LA TESSERA ELETTORALE O, PER IL VOTO CON TOKEN, IL WALLET
+            let voter_seed = if voter_data.election_card_number.is_empty() {
+                if voter_data.locked_amount > 0 {
+                    msg!("Voter {} has tokens still locked", pda_account.key);
+                    return Err(ProgramError::InvalidAccountData)
+                }
+                next_account_info(account_info_iter)?.key.to_bytes().to_vec()
+            } else {
+                voter_data.election_card_number.as_bytes().to_vec()
+            };
+            let (voter_pda, _voter_bump_seed) = Pubkey::find_program_address(
+                &[program_id.as_ref(), election_name.as_bytes(), &voter_seed],
+                program_id
+            );
+            if voter_pda != *pda_account.key {
+                msg!("Voter {} does not belong to {}", pda_account.key, election_name);
+                return Err(ProgramError::InvalidSeeds)
+            }
+        }
+
+        reclaimed_lamports += pda_account.lamports();
+        close_account(pda_account, recipient)?;
+        closed_accounts += 1;
+    }
+
+    msg!("Chiusi {} account, {} lamports a {}", closed_accounts, reclaimed_lamports, recipient.key);
+
+    Ok(())
+}
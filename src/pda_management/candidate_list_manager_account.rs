@@ -1,5 +1,3 @@
-use std::collections::HashMap;
-
 use solana_program::{
     entrypoint::ProgramResult,
     pubkey::Pubkey,
@@ -8,12 +6,11 @@ use solana_program::{
     system_instruction,
     program_error::ProgramError,
     sysvar::{rent::Rent, Sysvar},
-    program::invoke_signed,
-    borsh0_10::try_from_slice_unchecked
+    program::invoke_signed
 };
 
-use crate::state::candidate_list_state::CandidateListState;
-use borsh::BorshSerialize;
+use crate::state::{account_header::ACCOUNT_HEADER_LEN, candidate_list_state::{CandidateListState, CandidateEntry}};
+use crate::utilities::{account_size_utilities::save_with_realloc, account_state_utilities::{load_account, save_account}};
 
 pub fn generate_candidate_list_account(
     program_id: &Pubkey,
@@ -25,7 +22,8 @@ pub fn generate_candidate_list_account(
     let account_info_iter = &mut accounts.iter();
 
     // RECUPERA ACCOUNT FORNITI DAL CLIENT
-    let initializer: &AccountInfo<'_> = next_account_info(account_info_iter)?;
+    let _initializer: &AccountInfo<'_> = next_account_info(account_info_iter)?;
+    let payer = next_account_info(account_info_iter)?;
     let system_program = next_account_info(account_info_iter)?;
     let _election_pda_account = next_account_info(account_info_iter)?;
     let candidate_list_pda_account = next_account_info(account_info_iter)?;
@@ -34,7 +32,7 @@ pub fn generate_candidate_list_account(
 
     // DERIVA PDA
     let (candidate_list_pda, candidate_list_bump_seed) = Pubkey::find_program_address(
-        &[program_id.as_ref(), election_name.as_bytes().as_ref(),seed.as_bytes().as_ref()],
+        &[program_id.as_ref(), election_name.as_bytes(),seed.as_bytes()],
          program_id
         );
     //VALIDAZIONE DEL PDA 
@@ -42,8 +40,12 @@ pub fn generate_candidate_list_account(
         return Err(ProgramError::InvalidSeeds)
     }
     
-    //CALCOLA DIMENSIONE DELL'ACCOUNT
-    let account_len: usize = 1000;
+    //CALCOLA DIMENSIONE DELL'ACCOUNT DALLA LISTA VUOTA, CRESCE CON REALLOC AD OGNI CANDIDATO
+    let account_data = CandidateListState {
+        is_initialized: true,
+        ..Default::default()
+    };
+    let account_len: usize = ACCOUNT_HEADER_LEN + CandidateListState::space();
 
     //CALCOLA IL COSTO DI RENT 
     let rent = Rent::get()?;
@@ -52,26 +54,20 @@ pub fn generate_candidate_list_account(
     //CREA L'ACCOUNT
     invoke_signed(
         &system_instruction::create_account(
-            initializer.key,
+            payer.key,
             candidate_list_pda_account.key,
             rent_lamports,
             account_len.try_into().unwrap(),
             program_id
             ),
-        &[initializer.clone(),candidate_list_pda_account.clone(),system_program.clone()],
-        &[&[program_id.as_ref(),election_name.as_bytes().as_ref(),seed.as_bytes().as_ref(), &[candidate_list_bump_seed]]]
+        &[payer.clone(), candidate_list_pda_account.clone(),system_program.clone()],
+        &[&[program_id.as_ref(),election_name.as_bytes(),seed.as_bytes(), &[candidate_list_bump_seed]]]
         )?;
 
         msg!("PDA Created {}", candidate_list_pda);
 
-        msg!("Unpacking candidate-list account");
-        let mut account_data = try_from_slice_unchecked::<CandidateListState>(&candidate_list_pda_account.data.borrow()).unwrap();
-        msg!("Borrowed account data");
-
-        account_data.is_initialized = true;
-
         msg!("Serializing account");
-        account_data.serialize(&mut &mut candidate_list_pda_account.data.borrow_mut()[..])?;
+        save_account(&account_data, candidate_list_pda_account)?;
         msg!("Account serialized");
         msg!("Candidate list created for {}", election_name);
     
@@ -79,13 +75,18 @@ pub fn generate_candidate_list_account(
 
 }
 
-pub fn add_candidate_to_candidate_list(
+#[allow(clippy::too_many_arguments)]
+pub fn add_candidate_to_candidate_list<'a>(
     program_id: &Pubkey,
-    pda_account: &AccountInfo,
+    pda_account: &AccountInfo<'a>,
+    payer: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>,
     address_candidate: &Pubkey,
     election_name: String,
     candidate_first_name: String,
     candidate_last_name: String,
+    birth_date: String,
+    registration_slot: u64,
     seed: String
 ) -> ProgramResult {
     //CONTROLLA OWNER DEL PDA
@@ -95,7 +96,7 @@ pub fn add_candidate_to_candidate_list(
 
     //DERIVA PDA
     let(pda, _bump_seed) = Pubkey::find_program_address(
-        &[program_id.as_ref(),election_name.as_bytes().as_ref(),seed.as_bytes().as_ref()],
+        &[program_id.as_ref(),election_name.as_bytes(),seed.as_bytes()],
          program_id
         );
 
@@ -107,16 +108,22 @@ pub fn add_candidate_to_candidate_list(
 
     msg!("Retrieve candidate list account: {}",pda);
     //OTTIENE DATI DEL PDA
-    let mut account_data: CandidateListState = try_from_slice_unchecked::<CandidateListState>(&pda_account.data.borrow()).unwrap();
+    let mut account_data: CandidateListState = load_account::<CandidateListState>(pda_account)?;
 
     if !account_data.is_initialized {
         msg!("Account not initialized");
         return Err(ProgramError::InvalidAccountData)
     }
 
-    //AGGIUNGE CANDIDATO
+    //AGGIUNGE CANDIDATO CON IL PROSSIMO INDICE
     msg!("Updating candidate list...");
-    account_data.candidates.insert(format!("{} {}", candidate_first_name,candidate_last_name), *address_candidate);
+    //I NOMI DOPPI SONO GIA' STATI RIFIUTATI PRIMA DI CREARE L'ACCOUNT CANDIDATO
+    account_data.candidates.push(CandidateEntry {
+        name: format!("{} {}", candidate_first_name,candidate_last_name),
+        address: *address_candidate,
+        birth_date,
+        registration_slot,
+    });
 
     // let info = account_data.candidates.get(&format!("{} {}", candidate_first_name,candidate_last_name)).unwrap();
 
@@ -127,19 +134,49 @@ pub fn add_candidate_to_candidate_list(
     // }
 
     msg!("Serializing account");
-    account_data.serialize(&mut &mut pda_account.data.borrow_mut()[..])?;
+    save_with_realloc(&account_data, pda_account, payer, system_program)?;
     msg!("Account serialized");
 
     Ok(())
 }
 
-pub fn retrieve_candidate_account<'a>(
+//AGGIUNGE UN FINALISTA DEL PRIMO TURNO ALLA LISTA DEL BALLOTTAGGIO CON IL SUO ACCOUNT DEL BALLOTTAGGIO
+//Data di nascita e slot di registrazione restano quelli del primo turno
+#[allow(clippy::too_many_arguments)]
+pub fn add_finalist_to_candidate_list<'a>(
+    candidate_list_pda_account: &AccountInfo<'a>,
+    first_round_candidate_list_pda_account: &AccountInfo,
+    payer: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>,
+    candidate_info: String,
+    first_round_candidate_address: Pubkey,
+    candidate_address: Pubkey
+) -> ProgramResult {
+    let mut account_data = load_account::<CandidateListState>(candidate_list_pda_account)?;
+    let first_round_data = load_account::<CandidateListState>(first_round_candidate_list_pda_account)?;
+
+    if !account_data.is_initialized {
+        msg!("Account not initialized");
+        return Err(ProgramError::InvalidAccountData)
+    }
+
+    let first_round_entry = first_round_data.candidates.iter().find(|candidate| candidate.address == first_round_candidate_address).ok_or(ProgramError::InvalidArgument)?;
+    account_data.candidates.push(CandidateEntry {
+        name: candidate_info,
+        address: candidate_address,
+        ..first_round_entry.clone()
+    });
+
+    save_with_realloc(&account_data, candidate_list_pda_account, payer, system_program)
+}
+
+//LE SCHEDE INDICANO IL CANDIDATO CON IL SUO INDICE NELLA LISTA
+pub fn retrieve_candidate_account(
     pda_account: &AccountInfo,
-    candidate_first_name: String,
-    candidate_last_name: String,
-) -> Result<Pubkey,ProgramError>{
+    candidate_index: u32,
+) -> Result<CandidateEntry,ProgramError>{
 
-    let account_data: CandidateListState = try_from_slice_unchecked::<CandidateListState>(&pda_account.data.borrow()).unwrap();
+    let account_data: CandidateListState = load_account::<CandidateListState>(pda_account)?;
 
     
     // for (key,value) in account_data.candidates.clone()  {
@@ -147,18 +184,16 @@ pub fn retrieve_candidate_account<'a>(
     // }
     // msg!("Searching for {} {}",candidate_first_name,candidate_last_name);
     
-    let candidate_address = account_data.candidates.get(&format!("{} {}",candidate_first_name,candidate_last_name)).unwrap();
-   
-    let candidate_address_copy = *candidate_address;
-    return Ok(candidate_address_copy)
+    account_data.candidates.get(candidate_index as usize).cloned().ok_or(ProgramError::InvalidArgument)
 }
 
+//RESTITUISCE I CANDIDATI IN ORDINE DI INDICE
 pub fn retrieve_candidate_list (
     candidate_list_pda_account: &AccountInfo,
-) -> Result<HashMap<String,Pubkey>, ProgramError> {
+) -> Result<Vec<CandidateEntry>, ProgramError> {
 
-    let account_data = try_from_slice_unchecked::<CandidateListState>(&candidate_list_pda_account.data.borrow()).unwrap();
+    let account_data = load_account::<CandidateListState>(candidate_list_pda_account)?;
     let candidate_list_copy = account_data.candidates.clone();
 
-    return Ok(candidate_list_copy)
+    Ok(candidate_list_copy)
 }
\ No newline at end of file
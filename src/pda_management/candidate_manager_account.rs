@@ -1,3 +1,4 @@
+use chrono::NaiveDate;
 use solana_program::{
     entrypoint::ProgramResult,
     pubkey::Pubkey,
@@ -5,24 +6,28 @@ use solana_program::{
     account_info::{next_account_info, AccountInfo},
     system_instruction,
     program_error::ProgramError,
-    sysvar::{rent::Rent, Sysvar},
-    program::invoke_signed,
-    borsh0_10::try_from_slice_unchecked
+    sysvar::{rent::Rent, clock::Clock, Sysvar},
+    program::invoke_signed
 };
 
-use borsh::BorshSerialize;
 use crate::{
-    state::candidate_state::CandidateState, 
+    state::{account_header::ACCOUNT_HEADER_LEN, candidate_state::CandidateState, candidate_list_state::{CandidateListState, CandidateEntry}, election_account_state::ElectionAccountState}, 
     pda_management::{
         candidate_list_manager_account::add_candidate_to_candidate_list, 
-        election_manager_account::add_candidate_to_election
-    }};
+        election_manager_account::add_candidate_to_election,
+        vote_counters_manager_account::validate_vote_counters_account
+    },
+    utilities::{account_state_utilities::{load_account, save_account, pack_account}, account_size_utilities::check_realloc}};
+
+//SEED DEGLI ACCOUNT CANDIDATO, INSIEME AL PDA DELL'ELEZIONE E ALL'INDICE
+pub const CANDIDATE_SEED: &str = "candidate";
 
 pub fn add_candidate(
     program_id: &Pubkey,
     accounts: &[AccountInfo],
     first_name: String,
     last_name: String,
+    birth_date: String,
     election_name: String,
     seed: String
 ) -> ProgramResult {
@@ -32,49 +37,86 @@ pub fn add_candidate(
 
     // RECUPERA ACCOUNT FORNITI DALL'UTENTE
     let initializer = next_account_info(account_info_iter)?;
+    let payer = next_account_info(account_info_iter)?;
     let pda_account = next_account_info(account_info_iter)?;
     let pda_candidate_list = next_account_info(account_info_iter)?;
     let pda_election_account = next_account_info(account_info_iter)?;
     let system_program = next_account_info(account_info_iter)?;
+    let pda_vote_counters_account = next_account_info(account_info_iter)?;
+
+    // CHI NON PAGA DEVE COMUNQUE FIRMARE
+    if !initializer.is_signer {
+        return Err(ProgramError::MissingRequiredSignature)
+    }
 
     let candidate_first_name = first_name.clone();
     let candidate_last_name = last_name.clone();
 
+    // LA DATA DI NASCITA SERVE PER LO SPAREGGIO DEL CANDIDATO PIU' ANZIANO
+    if NaiveDate::parse_from_str(&birth_date, "%Y-%m-%d").is_err() {
+        msg!("Invalid birth date {}, expected YYYY-MM-DD", birth_date);
+        return Err(ProgramError::InvalidArgument)
+    }
+    let registration_slot = Clock::get()?.slot;
+
     // DERIVA PDA 
-    let (pda, bump_seed) = Pubkey::find_program_address(
-        &[initializer.key.as_ref(),election_name.as_bytes().as_ref(), first_name.as_bytes().as_ref(),last_name.as_bytes().as_ref()],
-         program_id
-        );
+    let (election_pda, _election_bump_seed) = Pubkey::find_program_address(
+        &[program_id.as_ref(), election_name.as_bytes()],
+        program_id
+    );
     let(candidate_list_pda,_candidate_bump_seed) = Pubkey::find_program_address(
-        &[program_id.as_ref(),election_name.as_bytes().as_ref(),seed.as_bytes().as_ref()],
+        &[program_id.as_ref(),election_name.as_bytes(),seed.as_bytes()],
         program_id
     );
-    //VALIDAZIONE DELL'ACCOUNT FORNITO
-    if candidate_list_pda != *pda_candidate_list.key {
+    //VALIDAZIONE DEGLI ACCOUNT FORNITI
+    if candidate_list_pda != *pda_candidate_list.key || election_pda != *pda_election_account.key {
         return Err(ProgramError::InvalidSeeds);
     }
-    
+    if pda_candidate_list.owner != program_id || pda_election_account.owner != program_id {
+        return Err(ProgramError::IllegalOwner)
+    }
 
-    // CALCOLA DIMESIONE DELL'ACCOUNT DA CREARE
-    let account_len: usize = 1 + (4 * first_name.len()) + (4 * last_name.len());
+    // SOLO L'AUTORITA' DELL'ELEZIONE REGISTRA I CANDIDATI
+    let mut election_data = load_account::<ElectionAccountState>(pda_election_account)?;
+    if !initializer.is_signer || election_data.authority != *initializer.key {
+        msg!("Only the election authority can add candidates");
+        return Err(ProgramError::MissingRequiredSignature)
+    }
 
-    // CALCOLA IL COSTO DI RENT
-    let rent = Rent::get()?;
-    let rent_lamports = rent.minimum_balance(account_len);
+    // IL NUOVO CANDIDATO PRENDE IL PRIMO INDICE LIBERO DELLA LISTA
+    let mut candidate_list_data = load_account::<CandidateListState>(pda_candidate_list)?;
+    let index = u32::try_from(candidate_list_data.candidates.len())
+        .map_err(|_| ProgramError::InvalidArgument)?;
+    let (pda, bump_seed) = candidate_address(program_id, &election_pda, index);
+    if pda != *pda_account.key {
+        msg!("Invalid seeds for candidate {}", index);
+        return Err(ProgramError::InvalidSeeds);
+    }
+    validate_vote_counters_account(program_id, pda_vote_counters_account, &election_name)?;
 
-    //CREA L'ACCOUNT
-    invoke_signed(
-        &system_instruction::create_account(
-            initializer.key,
-            pda_account.key,
-            rent_lamports,
-            account_len.try_into().unwrap(),
-            program_id
-        ),
-        &[initializer.clone(),pda_account.clone(), system_program.clone()],
-        &[&[initializer.key.as_ref(),election_name.as_bytes().as_ref(), first_name.as_bytes().as_ref(), last_name.as_bytes().as_ref(), &[bump_seed]]],
-    )?;
-    msg!("PDA Created: {}",pda);
+    // LE REGOLE DELLA LISTA E I LIMITI DI REALLOC SI VERIFICANO PRIMA DI CREARE L'ACCOUNT CANDIDATO:
+    // UN CANDIDATO RIFIUTATO NON DEVE OCCUPARE L'INDICE, CHE RESTA AL PROSSIMO CANDIDATO VALIDO
+    let name = format!("{} {}", first_name, last_name);
+    if candidate_list_data.index_of(&name).is_some() {
+        msg!("Candidate {} already registered", name);
+        return Err(ProgramError::InvalidArgument)
+    }
+    if !election_data.ballot_commitments.is_empty() {
+        msg!("Candidate list of {} is closed for the ballot order draw", election_data.name);
+        return Err(ProgramError::InvalidAccountData)
+    }
+    candidate_list_data.candidates.push(CandidateEntry {
+        name,
+        address: pda,
+        birth_date: birth_date.clone(),
+        registration_slot,
+    });
+    check_realloc(pda_candidate_list, pack_account(&candidate_list_data)?.len())?;
+    election_data.push_candidate(pda);
+    check_realloc(pda_election_account, pack_account(&election_data)?.len())?;
+
+
+    create_candidate_account(program_id, pda_account, payer, system_program, &election_pda, index, bump_seed, &first_name, &last_name)?;
 
     // //INIZIALIZZA L'ACCOUNT
     // let is_initialized = intialize_candidate_account(pda_account,first_name,last_name);
@@ -95,42 +137,172 @@ pub fn add_candidate(
 
     match intialize_candidate_account(pda_account, first_name, last_name) {
         Ok(()) => {
-            match add_candidate_to_candidate_list(program_id, pda_candidate_list, &pda, election_name, candidate_first_name, candidate_last_name, seed) {
+            match add_candidate_to_candidate_list(program_id, pda_candidate_list, payer, system_program, &pda, election_name, candidate_first_name, candidate_last_name, birth_date, registration_slot, seed) {
                 Ok(_) => {
-                    add_candidate_to_election(pda_election_account, pda)
+                    add_candidate_to_election(pda_election_account, pda_vote_counters_account, payer, system_program, pda)
                 },
-                Err(_) => {
+                //GLI ERRORI DI REALLOC (LIMITE DI 10 MB) ARRIVANO AL CLIENT
+                Err(error) => {
                     msg!("Errore");
-                    return Err(ProgramError::AccountBorrowFailed);
+                    Err(error)
                 },
             }
         }
         Err(_) => {
             msg!("Erorre");
-            return Err(ProgramError::AccountBorrowFailed)
+            Err(ProgramError::AccountBorrowFailed)
         }
     }
 }
 
+//CREA L'ACCOUNT DEL CANDIDATO ALL'INDICE index DELL'ELEZIONE, L'INDIRIZZO E' GIA' STATO VERIFICATO
+#[allow(clippy::too_many_arguments)]
+pub fn create_candidate_account<'a>(
+    program_id: &Pubkey,
+    pda_account: &AccountInfo<'a>,
+    payer: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>,
+    election_pda: &Pubkey,
+    index: u32,
+    bump_seed: u8,
+    first_name: &str,
+    last_name: &str
+) -> ProgramResult {
+    // CALCOLA DIMESIONE DELL'ACCOUNT DA CREARE
+    let account_len: usize = ACCOUNT_HEADER_LEN + CandidateState::space(first_name, last_name);
+
+    // CALCOLA IL COSTO DI RENT
+    let rent = Rent::get()?;
+    let rent_lamports = rent.minimum_balance(account_len);
+
+    //CREA L'ACCOUNT
+    invoke_signed(
+        &system_instruction::create_account(
+            payer.key,
+            pda_account.key,
+            rent_lamports,
+            account_len.try_into().unwrap(),
+            program_id
+        ),
+        &[payer.clone(), pda_account.clone(), system_program.clone()],
+        &[&[election_pda.as_ref(), CANDIDATE_SEED.as_bytes(), &index.to_le_bytes(), &[bump_seed]]],
+    )?;
+    msg!("PDA Created: {}",pda_account.key);
+
+    Ok(())
+}
+
+//DERIVA L'INDIRIZZO DEL CANDIDATO DAL PDA DELL'ELEZIONE E DAL SUO INDICE NELLA LISTA CANDIDATI
+//Non dipende da chi registra il candidato: un client lo ricava dal nome dell'elezione
+//e dalla posizione del candidato nella lista (CandidateListState::index_of).
+//Vale anche per il ballottaggio, che crea i suoi account per i finalisti agli indici 0 e 1.
+pub fn candidate_address(
+    program_id: &Pubkey,
+    election_pda: &Pubkey,
+    index: u32
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[election_pda.as_ref(), CANDIDATE_SEED.as_bytes(), &index.to_le_bytes()],
+        program_id
+    )
+}
+
 pub fn intialize_candidate_account (
     candidate_pda_account: &AccountInfo,
     first_name: String,
     last_name: String
 ) -> ProgramResult{
 
-    msg!("Unpacking candidate account");
-    let mut account_data = try_from_slice_unchecked::<CandidateState>(&candidate_pda_account.data.borrow()).unwrap();
-    msg!("Borrowed account data");
-
-    account_data.first_name = first_name;
-    account_data.last_name = last_name;
-    account_data.is_initialized = true;
+    //L'ACCOUNT E' APPENA STATO CREATO, LO STATO NON VIENE LETTO
+    let account_data = CandidateState {
+        is_initialized: true,
+        first_name,
+        last_name,
+    };
 
     msg!("Serializing account");
-    account_data.serialize(&mut &mut candidate_pda_account.data.borrow_mut()[..])?;
+    save_account(&account_data, candidate_pda_account)?;
     msg!("Account serialized");
 
 
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utilities::test_utilities::{TestAccount, account_infos, vote_counters, setup_syscall_stubs, invoked_instructions};
+
+    const ELECTION: &str = "Elettorale1";
+    const SEED: &str = "candidate-list";
+
+    //ACCOUNT DI AddCandidate PER UN'ELEZIONE CON "Marco Togni" ALL'INDICE 0, FIRMATI DA signer
+    fn candidate_accounts(program_id: &Pubkey, signer: Pubkey, authority: Pubkey) -> Vec<TestAccount> {
+        let (election_pda, _) = Pubkey::find_program_address(&[program_id.as_ref(), ELECTION.as_bytes()], program_id);
+        let (candidate_list_pda, _) = Pubkey::find_program_address(&[program_id.as_ref(), ELECTION.as_bytes(), SEED.as_bytes()], program_id);
+        let (vote_counters_pda, _) = Pubkey::find_program_address(&[program_id.as_ref(), ELECTION.as_bytes(), b"counters"], program_id);
+        let first_candidate = candidate_address(program_id, &election_pda, 0).0;
+
+        let election_data = ElectionAccountState {
+            is_initialized: true,
+            name: ELECTION.to_string(),
+            authority,
+            votes: vec![0],
+            headcount: vec![0],
+            credits: vec![0],
+            candidates: vec![first_candidate],
+            ..Default::default()
+        };
+        let candidate_list = CandidateListState {
+            is_initialized: true,
+            candidates: vec![CandidateEntry {
+                name: "Marco Togni".to_string(),
+                address: first_candidate,
+                birth_date: "1970-01-01".to_string(),
+                registration_slot: 0,
+            }],
+        };
+        vec![
+            TestAccount::signer(signer),
+            TestAccount::signer(signer),
+            TestAccount::empty(candidate_address(program_id, &election_pda, 1).0),
+            TestAccount::with_state(program_id, candidate_list_pda, &candidate_list),
+            TestAccount::with_state(program_id, election_pda, &election_data),
+            TestAccount::empty(solana_program::system_program::id()),
+            TestAccount {
+                owner: *program_id,
+                data: vote_counters(1),
+                ..TestAccount::empty(vote_counters_pda)
+            },
+        ]
+    }
+
+    fn register(program_id: &Pubkey, accounts: &mut [TestAccount], first_name: &str, last_name: &str) -> ProgramResult {
+        add_candidate(program_id, &account_infos(accounts), first_name.to_string(), last_name.to_string(), "1980-05-05".to_string(), ELECTION.to_string(), SEED.to_string())
+    }
+
+    #[test]
+    fn rejected_duplicate_leaves_the_index_free() {
+        setup_syscall_stubs();
+        let program_id = Pubkey::new_unique();
+        let authority = Pubkey::new_unique();
+        let mut accounts = candidate_accounts(&program_id, authority, authority);
+
+        assert_eq!(register(&program_id, &mut accounts, "Marco", "Togni"), Err(ProgramError::InvalidArgument));
+        assert!(invoked_instructions().is_empty());
+        assert!(accounts[2].data.is_empty());
+        assert_eq!(load_account::<CandidateListState>(&accounts[3].account_info()).unwrap().candidates.len(), 1);
+    }
+
+    #[test]
+    fn only_the_authority_adds_candidates() {
+        setup_syscall_stubs();
+        let program_id = Pubkey::new_unique();
+        let mut accounts = candidate_accounts(&program_id, Pubkey::new_unique(), Pubkey::new_unique());
+
+        assert_eq!(register(&program_id, &mut accounts, "Luca", "Bianchi"), Err(ProgramError::MissingRequiredSignature));
+        assert!(invoked_instructions().is_empty());
+        assert!(accounts[2].data.is_empty());
+    }
+}
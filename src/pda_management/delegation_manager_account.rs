@@ -0,0 +1,453 @@
+use std::collections::{HashMap, HashSet};
+
+use solana_program::{
+    entrypoint::ProgramResult,
+    pubkey::Pubkey,
+    msg,
+    account_info::{next_account_info, AccountInfo},
+    system_instruction,
+    program_error::ProgramError,
+    sysvar::{rent::Rent, Sysvar},
+    program::invoke_signed
+};
+
+use crate::{
+    state::{
+        account_header::ACCOUNT_HEADER_LEN,
+        delegation_state::{DelegationScope, DelegationState, DelegationCounterState},
+        election_account_state::{ElectionAccountState, VotingMode},
+        voter_account_state::VoterAccountState
+    },
+    pda_management::roll_manager_account::{check_roll_entry_signer, retrieve_roll_entry_weight, roll_election_name},
+    utilities::{election_account_utilities::is_election_closed, account_size_utilities::save_with_realloc, account_state_utilities::{load_account, save_account}}
+};
+
+//DERIVA IL PDA DELLA DELEGA DI UN VOTANTE PER UN'ELEZIONE O PER UN TEMA
+pub fn find_delegation_address(
+    program_id: &Pubkey,
+    scope: DelegationScope,
+    scope_name: &str,
+    electoral_card_number: &str
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[program_id.as_ref(), "delegation".as_bytes(), &[scope as u8], scope_name.as_bytes(), electoral_card_number.as_bytes()],
+        program_id
+    )
+}
+
+//DERIVA IL PDA DEL CONTATORE DELLE DELEGHE ATTIVE DI UN'ELEZIONE O DI UN TEMA
+pub fn find_delegation_counter_address(
+    program_id: &Pubkey,
+    scope: DelegationScope,
+    scope_name: &str
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[program_id.as_ref(), "delegation-counter".as_bytes(), &[scope as u8], scope_name.as_bytes()],
+        program_id
+    )
+}
+
+//LA DELEGA E' AMMESSA SOLO PER IL VOTO PER TESTA O PONDERATO, SENZA CIRCOSCRIZIONI
+pub fn supports_delegation(
+    election_data: &ElectionAccountState
+) -> bool {
+    matches!(election_data.voting_mode, VotingMode::Standard | VotingMode::Weighted) && election_data.number_of_districts == 0
+}
+
+//LEGGE IL NUMERO DI DELEGHE ATTIVE, ZERO SE IL CONTATORE NON E' ANCORA STATO CREATO
+fn retrieve_active_delegations(
+    program_id: &Pubkey,
+    counter_pda_account: &AccountInfo,
+    scope: DelegationScope,
+    scope_name: &str
+) -> Result<u32, ProgramError> {
+    let (counter_pda, _counter_bump_seed) = find_delegation_counter_address(program_id, scope, scope_name);
+    if counter_pda != *counter_pda_account.key {
+        msg!("Invalid seeds for delegation counter");
+        return Err(ProgramError::InvalidSeeds)
+    }
+    if counter_pda_account.data_is_empty() {
+        return Ok(0)
+    }
+    if counter_pda_account.owner != program_id {
+        return Err(ProgramError::IllegalOwner)
+    }
+
+    Ok(load_account::<DelegationCounterState>(counter_pda_account)?.active)
+}
+
+//DELEGA IL VOTO A UN ALTRO VOTANTE ISCRITTO, PER L'ELEZIONE O PER IL SUO TEMA
+//Firma il titolare della tessera del delegante, registrato nelle liste elettorali
+pub fn add_delegation(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    election_name: String,
+    electoral_card_number: String,
+    delegate_card_number: String,
+    scope: DelegationScope
+) -> ProgramResult {
+
+    //CREA ITERATORE SU ACCOUNTS
+    let account_info_iter = &mut accounts.iter();
+
+    //RECUPERA ACCOUNT FORNITI DAL CLIENT
+    let initializer = next_account_info(account_info_iter)?;
+    let payer = next_account_info(account_info_iter)?;
+    let system_program = next_account_info(account_info_iter)?;
+    let election_pda_account = next_account_info(account_info_iter)?;
+    let delegation_pda_account = next_account_info(account_info_iter)?;
+    let delegator_roll_entry_pda_account = next_account_info(account_info_iter)?;
+    let delegate_roll_entry_pda_account = next_account_info(account_info_iter)?;
+    let counter_pda_account = next_account_info(account_info_iter)?;
+
+    let (election_pda, _election_bump_seed) = Pubkey::find_program_address(
+        &[program_id.as_ref(), election_name.as_bytes()],
+        program_id
+    );
+    if election_pda != *election_pda_account.key {
+        msg!("Invalid seeds for PDA");
+        return Err(ProgramError::InvalidSeeds)
+    }
+    if election_pda_account.owner != program_id {
+        return Err(ProgramError::IllegalOwner)
+    }
+
+    let election_data = load_account::<ElectionAccountState>(election_pda_account)?;
+
+    if !supports_delegation(&election_data) {
+        msg!("Delegation is not supported for election {}", election_name);
+        return Err(ProgramError::InvalidArgument)
+    }
+    if is_election_closed(&election_data.end_date)? {
+        msg!("Election {} is closed", election_name);
+        return Err(ProgramError::InvalidArgument)
+    }
+    if electoral_card_number == delegate_card_number {
+        msg!("A voter cannot delegate to themselves");
+        return Err(ProgramError::InvalidArgument)
+    }
+
+    let scope_name = match scope {
+        DelegationScope::Election => election_name.clone(),
+        DelegationScope::Topic => {
+            if election_data.topic.is_empty() {
+                msg!("Election {} has no topic", election_name);
+                return Err(ProgramError::InvalidArgument)
+            }
+            election_data.topic.clone()
+        }
+    };
+
+    //DELEGANTE E DELEGATO DEVONO ESSERE ISCRITTI NELLE LISTE ELETTORALI, IL DELEGANTE FIRMA
+    let roll_election_name = roll_election_name(&election_data);
+    let weight = check_roll_entry_signer(program_id, delegator_roll_entry_pda_account, roll_election_name, &electoral_card_number, initializer)?.weight;
+    retrieve_roll_entry_weight(program_id, delegate_roll_entry_pda_account, roll_election_name, &delegate_card_number)?;
+
+    let (delegation_pda, delegation_bump_seed) = find_delegation_address(program_id, scope, &scope_name, &electoral_card_number);
+    if delegation_pda != *delegation_pda_account.key {
+        msg!("Invalid seeds for delegation");
+        return Err(ProgramError::InvalidSeeds)
+    }
+
+    let (counter_pda, counter_bump_seed) = find_delegation_counter_address(program_id, scope, &scope_name);
+    if counter_pda != *counter_pda_account.key {
+        msg!("Invalid seeds for delegation counter");
+        return Err(ProgramError::InvalidSeeds)
+    }
+
+    //LA PRIMA DELEGA CREA L'ACCOUNT, LE SUCCESSIVE LA SOSTITUISCONO
+    let was_active = if delegation_pda_account.data_is_empty() {
+        let account_len: usize = ACCOUNT_HEADER_LEN + DelegationState::space(&scope_name, &electoral_card_number, &delegate_card_number);
+
+        let rent = Rent::get()?;
+        let rent_lamports = rent.minimum_balance(account_len);
+
+        invoke_signed(
+            &system_instruction::create_account(
+                payer.key,
+                delegation_pda_account.key,
+                rent_lamports,
+                account_len.try_into().unwrap(),
+                program_id
+            ),
+            &[payer.clone(), delegation_pda_account.clone(), system_program.clone()],
+            &[&[program_id.as_ref(), "delegation".as_bytes(), &[scope as u8], scope_name.as_bytes(), electoral_card_number.as_bytes(), &[delegation_bump_seed]]]
+        )?;
+
+        msg!("PDA Created: {}", delegation_pda);
+        false
+    } else if delegation_pda_account.owner != program_id {
+        return Err(ProgramError::IllegalOwner)
+    } else {
+        //LA DELEGA SOSTITUITA DEVE ESSERE UN ACCOUNT DELEGA VALIDO
+        load_account::<DelegationState>(delegation_pda_account)?.is_active
+    };
+
+    //UNA DELEGA NUOVA O RIATTIVATA INCREMENTA IL CONTATORE, UNA SOSTITUITA NO
+    let mut counter_data = if counter_pda_account.data_is_empty() {
+        let account_len: usize = ACCOUNT_HEADER_LEN + DelegationCounterState::space(&scope_name);
+
+        let rent = Rent::get()?;
+        let rent_lamports = rent.minimum_balance(account_len);
+
+        invoke_signed(
+            &system_instruction::create_account(
+                payer.key,
+                counter_pda_account.key,
+                rent_lamports,
+                account_len.try_into().unwrap(),
+                program_id
+            ),
+            &[payer.clone(), counter_pda_account.clone(), system_program.clone()],
+            &[&[program_id.as_ref(), "delegation-counter".as_bytes(), &[scope as u8], scope_name.as_bytes(), &[counter_bump_seed]]]
+        )?;
+
+        msg!("PDA Created: {}", counter_pda);
+        DelegationCounterState {
+            is_initialized: true,
+            scope,
+            scope_name: scope_name.clone(),
+            active: 0,
+        }
+    } else if counter_pda_account.owner != program_id {
+        return Err(ProgramError::IllegalOwner)
+    } else {
+        load_account::<DelegationCounterState>(counter_pda_account)?
+    };
+    if !was_active {
+        counter_data.active = counter_data.active.checked_add(1).ok_or(ProgramError::InvalidArgument)?;
+        save_account(&counter_data, counter_pda_account)?;
+    }
+
+    let account_data = DelegationState {
+        is_initialized: true,
+        scope,
+        scope_name,
+        delegator: electoral_card_number,
+        delegate: delegate_card_number,
+        weight,
+        is_active: true,
+    };
+
+    msg!("{} delega {}", account_data.delegator, account_data.delegate);
+    //UN NUOVO DELEGATO CON TESSERA PIU' LUNGA INGRANDISCE L'ACCOUNT
+    save_with_realloc(&account_data, delegation_pda_account, payer, system_program)?;
+
+    Ok(())
+}
+
+//REVOCA UNA DELEGA PRIMA DELLA CHIUSURA DELL'ELEZIONE
+//Firma il titolare della tessera del delegante, registrato nelle liste elettorali
+pub fn revoke_delegation(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    election_name: String,
+    electoral_card_number: String,
+    scope: DelegationScope
+) -> ProgramResult {
+
+    let account_info_iter = &mut accounts.iter();
+
+    let initializer = next_account_info(account_info_iter)?;
+    let election_pda_account = next_account_info(account_info_iter)?;
+    let delegation_pda_account = next_account_info(account_info_iter)?;
+    let delegator_roll_entry_pda_account = next_account_info(account_info_iter)?;
+    let counter_pda_account = next_account_info(account_info_iter)?;
+
+    let (election_pda, _election_bump_seed) = Pubkey::find_program_address(
+        &[program_id.as_ref(), election_name.as_bytes()],
+        program_id
+    );
+    if election_pda != *election_pda_account.key {
+        msg!("Invalid seeds for PDA");
+        return Err(ProgramError::InvalidSeeds)
+    }
+    if election_pda_account.owner != program_id || delegation_pda_account.owner != program_id {
+        return Err(ProgramError::IllegalOwner)
+    }
+
+    let election_data = load_account::<ElectionAccountState>(election_pda_account)?;
+
+    if is_election_closed(&election_data.end_date)? {
+        msg!("Election {} is closed", election_name);
+        return Err(ProgramError::InvalidArgument)
+    }
+
+    check_roll_entry_signer(program_id, delegator_roll_entry_pda_account, roll_election_name(&election_data), &electoral_card_number, initializer)?;
+
+    let scope_name = match scope {
+        DelegationScope::Election => election_name,
+        DelegationScope::Topic => election_data.topic,
+    };
+    let (delegation_pda, _delegation_bump_seed) = find_delegation_address(program_id, scope, &scope_name, &electoral_card_number);
+    let (counter_pda, _counter_bump_seed) = find_delegation_counter_address(program_id, scope, &scope_name);
+    if delegation_pda != *delegation_pda_account.key || counter_pda != *counter_pda_account.key {
+        msg!("Invalid seeds for delegation");
+        return Err(ProgramError::InvalidSeeds)
+    }
+    if counter_pda_account.owner != program_id {
+        return Err(ProgramError::IllegalOwner)
+    }
+
+    let mut account_data = load_account::<DelegationState>(delegation_pda_account)?;
+    if !account_data.is_active {
+        msg!("Delegation of {} is not active", electoral_card_number);
+        return Err(ProgramError::InvalidArgument)
+    }
+    account_data.is_active = false;
+    save_account(&account_data, delegation_pda_account)?;
+
+    let mut counter_data = load_account::<DelegationCounterState>(counter_pda_account)?;
+    counter_data.active = counter_data.active.checked_sub(1).ok_or(ProgramError::InvalidAccountData)?;
+    save_account(&counter_data, counter_pda_account)?;
+
+    msg!("Delega di {} revocata", electoral_card_number);
+
+    Ok(())
+}
+
+//LEGGE IL CANDIDATO VOTATO DIRETTAMENTE DA UNA TESSERA, SE HA VOTATO
+fn retrieve_direct_vote(
+    program_id: &Pubkey,
+    voter_pda_account: &AccountInfo,
+    election_name: &str,
+    electoral_card_number: &str
+) -> Result<Option<Pubkey>, ProgramError> {
+    let (voter_pda, _voter_bump_seed) = Pubkey::find_program_address(
+        &[program_id.as_ref(), election_name.as_bytes(), electoral_card_number.as_bytes()],
+        program_id
+    );
+    if voter_pda != *voter_pda_account.key {
+        msg!("Invalid seeds for voter {}", electoral_card_number);
+        return Err(ProgramError::InvalidSeeds)
+    }
+    if voter_pda_account.owner != program_id || voter_pda_account.data_is_empty() {
+        return Ok(None)
+    }
+
+    let account_data = load_account::<VoterAccountState>(voter_pda_account)?;
+
+    Ok(Some(account_data.voted))
+}
+
+//RISOLVE LE DELEGHE TRANSITIVE AL MOMENTO DELLO SCRUTINIO
+//Gli account restanti sono il contatore delle deleghe dell'elezione, quello del tema se l'elezione ne ha uno,
+//poi terne (delega, account votante del delegante, account votante del delegato).
+//Devono essere fornite tutte le deleghe attive registrate nei contatori.
+//Chi ha votato direttamente non viene contato tramite delega; le catene cicliche o senza voto finale si astengono.
+//Restituisce per ogni candidato il peso e il numero di votanti ricevuti tramite delega.
+pub fn resolve_delegated_votes<'a, 'b: 'a>(
+    program_id: &Pubkey,
+    election_data: &ElectionAccountState,
+    account_info_iter: &mut impl Iterator<Item = &'a AccountInfo<'b>>
+) -> Result<HashMap<Pubkey, (i64, i64)>, ProgramError> {
+
+    if !supports_delegation(election_data) {
+        return Ok(HashMap::new())
+    }
+
+    //DELEGHE ATTIVE ATTESE PER L'ELEZIONE E PER IL TEMA
+    let election_counter_pda_account = next_account_info(account_info_iter)?;
+    let expected_election = retrieve_active_delegations(program_id, election_counter_pda_account, DelegationScope::Election, &election_data.name)?;
+    let expected_topic = if election_data.topic.is_empty() {
+        0
+    } else {
+        let topic_counter_pda_account = next_account_info(account_info_iter)?;
+        retrieve_active_delegations(program_id, topic_counter_pda_account, DelegationScope::Topic, &election_data.topic)?
+    };
+    let (mut supplied_election, mut supplied_topic): (u32, u32) = (0, 0);
+    let mut supplied: HashSet<Pubkey> = HashSet::new();
+
+    //DELEGHE ATTIVE PER DELEGANTE: LA DELEGA PER ELEZIONE PREVALE SU QUELLA PER TEMA
+    let mut delegations: HashMap<String, (DelegationScope, String, u64)> = HashMap::new();
+    let mut direct_votes: HashMap<String, Option<Pubkey>> = HashMap::new();
+
+    while let Ok(delegation_pda_account) = next_account_info(account_info_iter) {
+        let delegator_voter_pda_account = next_account_info(account_info_iter)?;
+        let delegate_voter_pda_account = next_account_info(account_info_iter)?;
+
+        if delegation_pda_account.owner != program_id {
+            return Err(ProgramError::IllegalOwner)
+        }
+        if !supplied.insert(*delegation_pda_account.key) {
+            msg!("Delegation {} passed twice", delegation_pda_account.key);
+            return Err(ProgramError::InvalidArgument)
+        }
+        let delegation = load_account::<DelegationState>(delegation_pda_account)?;
+
+        let (delegation_pda, _delegation_bump_seed) = find_delegation_address(program_id, delegation.scope, &delegation.scope_name, &delegation.delegator);
+        if delegation_pda != *delegation_pda_account.key || !delegation.is_initialized {
+            msg!("Invalid delegation account {}", delegation_pda_account.key);
+            return Err(ProgramError::InvalidSeeds)
+        }
+        let applies = match delegation.scope {
+            DelegationScope::Election => delegation.scope_name == election_data.name,
+            DelegationScope::Topic => !election_data.topic.is_empty() && delegation.scope_name == election_data.topic,
+        };
+        if !applies {
+            msg!("Delegation {} does not apply to {}", delegation_pda_account.key, election_data.name);
+            return Err(ProgramError::InvalidArgument)
+        }
+
+        direct_votes.insert(delegation.delegator.clone(), retrieve_direct_vote(program_id, delegator_voter_pda_account, &election_data.name, &delegation.delegator)?);
+        direct_votes.insert(delegation.delegate.clone(), retrieve_direct_vote(program_id, delegate_voter_pda_account, &election_data.name, &delegation.delegate)?);
+
+        if !delegation.is_active {
+            continue;
+        }
+        match delegation.scope {
+            DelegationScope::Election => supplied_election += 1,
+            DelegationScope::Topic => supplied_topic += 1,
+        }
+        match delegations.get(&delegation.delegator) {
+            Some((scope, _, _)) if *scope == delegation.scope => {
+                msg!("Delegation of {} passed twice", delegation.delegator);
+                return Err(ProgramError::InvalidArgument)
+            }
+            Some((DelegationScope::Election, _, _)) => continue,
+            _ => {
+                delegations.insert(delegation.delegator, (delegation.scope, delegation.delegate, delegation.weight));
+            }
+        }
+    }
+
+    //LE DELEGHE FORNITE SONO ACCOUNT DISTINTI: CONTEGGI UGUALI AI CONTATORI = TUTTE FORNITE
+    if supplied_election != expected_election || supplied_topic != expected_topic {
+        msg!("Expected {} election and {} topic delegations, got {} and {}", expected_election, expected_topic, supplied_election, supplied_topic);
+        return Err(ProgramError::NotEnoughAccountKeys)
+    }
+
+    let mut delegated_votes: HashMap<Pubkey, (i64, i64)> = HashMap::new();
+
+    for (delegator, (_, delegate, weight)) in &delegations {
+        //IL VOTO DIRETTO PREVALE SULLA DELEGA
+        if let Some(Some(_)) = direct_votes.get(delegator) {
+            continue;
+        }
+
+        let mut visited: HashSet<&String> = HashSet::from([delegator]);
+        let mut current = delegate;
+        let resolved = loop {
+            if !visited.insert(current) {
+                msg!("Delegation cycle from {}", delegator);
+                break None;
+            }
+            //UNA SCHEDA SIGILLATA NON RIVELATA NON HA CANDIDATO: LA CATENA SI ASTIENE
+            if let Some(Some(candidate_address)) = direct_votes.get(current) {
+                break (*candidate_address != Pubkey::default()).then_some(*candidate_address);
+            }
+            match delegations.get(current) {
+                Some((_, next, _)) => current = next,
+                None => break None,
+            }
+        };
+
+        if let Some(candidate_address) = resolved {
+            let entry = delegated_votes.entry(candidate_address).or_insert((0, 0));
+            entry.0 += *weight as i64;
+            entry.1 += 1;
+        }
+    }
+
+    Ok(delegated_votes)
+}
@@ -0,0 +1,356 @@
+use std::collections::{BTreeMap, HashSet};
+
+use solana_program::{
+    entrypoint::ProgramResult,
+    pubkey::Pubkey,
+    msg,
+    account_info::{next_account_info, AccountInfo},
+    system_instruction,
+    program_error::ProgramError,
+    sysvar::{rent::Rent, Sysvar},
+    program::invoke_signed
+};
+
+use crate::{
+    state::{account_header::ACCOUNT_HEADER_LEN, district_state::DistrictState, election_account_state::ElectionAccountState, result_state::{ResultState, RankedEntry}},
+    pda_management::{
+        candidate_list_manager_account::retrieve_candidate_list,
+        result_manager_account::{add_number_of_votes, add_headcount_results, sort_and_add_results, break_ties, evaluate_outcome},
+        election_manager_account::{check_tally_allowed, finalize_tally, voting_started}
+    },
+    utilities::{account_size_utilities::save_with_realloc, account_state_utilities::{load_account, save_account, pack_account}}
+};
+
+pub fn add_district(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    election_name: String,
+    district_name: String
+) -> ProgramResult {
+
+    let district_seed = String::from("district");
+    let result_seed = String::from("result");
+
+    //CREA ITERATORE SU ACCOUNTS
+    let account_info_iter = &mut accounts.iter();
+
+    //RECUPERA ACCOUNT FORNITI DAL CLIENT
+    let initializer = next_account_info(account_info_iter)?;
+    let payer = next_account_info(account_info_iter)?;
+    let system_program = next_account_info(account_info_iter)?;
+    let election_pda_account = next_account_info(account_info_iter)?;
+    let district_pda_account = next_account_info(account_info_iter)?;
+    let district_result_pda_account = next_account_info(account_info_iter)?;
+
+    //DERIVA PDA
+    let (election_pda, _election_bump_seed) = Pubkey::find_program_address(
+        &[program_id.as_ref(), election_name.as_bytes()],
+        program_id
+    );
+    let (district_pda, district_bump_seed) = Pubkey::find_program_address(
+        &[program_id.as_ref(), election_name.as_bytes(), district_seed.as_bytes(), district_name.as_bytes()],
+        program_id
+    );
+    let (district_result_pda, district_result_bump_seed) = Pubkey::find_program_address(
+        &[program_id.as_ref(), election_name.as_bytes(), district_seed.as_bytes(), district_name.as_bytes(), result_seed.as_bytes()],
+        program_id
+    );
+
+    //VALIDAZIONE DEGLI ACCOUNT
+    if election_pda != *election_pda_account.key || district_pda != *district_pda_account.key || district_result_pda != *district_result_pda_account.key {
+        msg!("Invalid seeds for PDA");
+        return Err(ProgramError::InvalidSeeds)
+    }
+    if election_pda_account.owner != program_id {
+        return Err(ProgramError::IllegalOwner)
+    }
+
+    let mut election_data = load_account::<ElectionAccountState>(election_pda_account)?;
+
+    //SOLO L'AUTORITA' DELL'ELEZIONE PUO' CREARE CIRCOSCRIZIONI, PRIMA DEL VOTO
+    if !initializer.is_signer || election_data.authority != *initializer.key {
+        msg!("Only the election authority can add districts");
+        return Err(ProgramError::MissingRequiredSignature)
+    }
+    if voting_started(&election_data)? {
+        msg!("Districts cannot be added after voting has started");
+        return Err(ProgramError::InvalidAccountData)
+    }
+
+    //CALCOLA DIMENSIONE DEGLI ACCOUNT DAL LORO STATO INIZIALE, CRESCONO CON REALLOC
+    let district_data = DistrictState {
+        is_initialized: true,
+        name: district_name.clone(),
+        ..Default::default()
+    };
+    let district_account_len: usize = ACCOUNT_HEADER_LEN + DistrictState::space(&district_data.name);
+    let district_result_account_len: usize = pack_account(&ResultState::default())?.len();
+
+    //CALCOLA IL COSTO DI RENT
+    let rent = Rent::get()?;
+
+    //CREA GLI ACCOUNT
+    invoke_signed(
+        &system_instruction::create_account(
+            payer.key,
+            district_pda_account.key,
+            rent.minimum_balance(district_account_len),
+            district_account_len.try_into().unwrap(),
+            program_id
+        ),
+        &[payer.clone(), district_pda_account.clone(), system_program.clone()],
+        &[&[program_id.as_ref(), election_name.as_bytes(), district_seed.as_bytes(), district_name.as_bytes(), &[district_bump_seed]]]
+    )?;
+    invoke_signed(
+        &system_instruction::create_account(
+            payer.key,
+            district_result_pda_account.key,
+            rent.minimum_balance(district_result_account_len),
+            district_result_account_len.try_into().unwrap(),
+            program_id
+        ),
+        &[payer.clone(), district_result_pda_account.clone(), system_program.clone()],
+        &[&[program_id.as_ref(), election_name.as_bytes(), district_seed.as_bytes(), district_name.as_bytes(), result_seed.as_bytes(), &[district_result_bump_seed]]]
+    )?;
+
+    msg!("PDA Created: {}", district_pda);
+
+    //INIZIALIZZA LA CIRCOSCRIZIONE E IL SUO RISULTATO VUOTO
+    save_account(&district_data, district_pda_account)?;
+    save_account(&ResultState::default(), district_result_pda_account)?;
+
+    election_data.number_of_districts += 1;
+    save_account(&election_data, election_pda_account)?;
+
+    Ok(())
+}
+
+pub fn validate_district_account(
+    program_id: &Pubkey,
+    district_pda_account: &AccountInfo,
+    election_name: &str,
+    district_name: &str
+) -> ProgramResult {
+    let (district_pda, _district_bump_seed) = Pubkey::find_program_address(
+        &[program_id.as_ref(), election_name.as_bytes(), "district".as_bytes(), district_name.as_bytes()],
+        program_id
+    );
+
+    if district_pda != *district_pda_account.key {
+        msg!("Invalid seeds for district {}", district_name);
+        return Err(ProgramError::InvalidSeeds)
+    }
+    if district_pda_account.owner != program_id {
+        return Err(ProgramError::IllegalOwner)
+    }
+
+    Ok(())
+}
+
+pub fn add_vote_to_district<'a>(
+    district_pda_account: &AccountInfo<'a>,
+    payer: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>,
+    slot: usize,
+    weight: i64
+) -> ProgramResult {
+    let mut account_data = load_account::<DistrictState>(district_pda_account)?;
+
+    if !account_data.is_initialized {
+        msg!("District not initialized");
+        return Err(ProgramError::UninitializedAccount)
+    }
+
+    //I CONTATORI CRESCONO FINO ALL'INDICE DEL CANDIDATO VOTATO
+    if account_data.votes.len() <= slot {
+        account_data.votes.resize(slot + 1, 0);
+        account_data.headcount.resize(slot + 1, 0);
+    }
+    account_data.votes[slot] += weight;
+    account_data.headcount[slot] += 1;
+    account_data.number_of_votes += 1;
+    account_data.total_weight += weight;
+
+    save_with_realloc(&account_data, district_pda_account, payer, system_program)
+}
+
+//POPOLA L'ACCOUNT RISULTATI DELLA CIRCOSCRIZIONE
+pub fn counting_district_votes(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    election_name: String,
+    district_name: String
+) -> ProgramResult {
+
+    let account_info_iter = &mut accounts.iter();
+
+    let initializer = next_account_info(account_info_iter)?;
+    let payer = next_account_info(account_info_iter)?;
+    let election_pda_account = next_account_info(account_info_iter)?;
+    let candidate_list_pda_account = next_account_info(account_info_iter)?;
+    let district_pda_account = next_account_info(account_info_iter)?;
+    let district_result_pda_account = next_account_info(account_info_iter)?;
+    let system_program = next_account_info(account_info_iter)?;
+
+    let (election_pda, _election_bump_seed) = Pubkey::find_program_address(
+        &[program_id.as_ref(), election_name.as_bytes()],
+        program_id
+    );
+    let (candidate_list_pda, _candidate_list_bump_seed) = Pubkey::find_program_address(
+        &[program_id.as_ref(), election_name.as_bytes(), "candidate-list".as_bytes()],
+        program_id
+    );
+    let (district_result_pda, _district_result_bump_seed) = Pubkey::find_program_address(
+        &[program_id.as_ref(), election_name.as_bytes(), "district".as_bytes(), district_name.as_bytes(), "result".as_bytes()],
+        program_id
+    );
+    if election_pda != *election_pda_account.key || candidate_list_pda != *candidate_list_pda_account.key || district_result_pda != *district_result_pda_account.key {
+        msg!("Invalid seeds for PDA");
+        return Err(ProgramError::InvalidSeeds)
+    }
+    if election_pda_account.owner != program_id {
+        return Err(ProgramError::IllegalOwner)
+    }
+    validate_district_account(program_id, district_pda_account, &election_name, &district_name)?;
+
+    //I RISULTATI DELLE CIRCOSCRIZIONI SEGUONO LE REGOLE DELLO SCRUTINIO NAZIONALE
+    let election_data = load_account::<ElectionAccountState>(election_pda_account)?;
+    check_tally_allowed(initializer, &election_data)?;
+
+    //RECUPERA I CANDIDATI IN ORDINE DI INDICE E I CONTATORI DELLA CIRCOSCRIZIONE
+    let candidate_list = retrieve_candidate_list(candidate_list_pda_account)?;
+    let district_data = load_account::<DistrictState>(district_pda_account)?;
+
+    let mut vote_counts: Vec<RankedEntry> = Vec::with_capacity(candidate_list.len());
+    let mut headcount: BTreeMap<String, i64> = BTreeMap::new();
+    for (index, candidate) in candidate_list.into_iter().enumerate() {
+        headcount.insert(candidate.name.clone(), district_data.headcount.get(index).copied().unwrap_or(0));
+        vote_counts.push(RankedEntry {
+            rank: 0,
+            index: index as u32,
+            candidate: candidate.address,
+            name: candidate.name,
+            votes: district_data.votes.get(index).copied().unwrap_or(0),
+            share: 0,
+            tied: false,
+        });
+    }
+
+    msg!("Risultati della circoscrizione {}", district_name);
+    let mut result_data = ResultState::default();
+    add_number_of_votes(&mut result_data, district_data.number_of_votes, district_data.total_weight);
+    add_headcount_results(&mut result_data, &headcount);
+    sort_and_add_results(&mut result_data, vote_counts);
+
+    save_with_realloc(&result_data, district_result_pda_account, payer, system_program)
+}
+
+//AGGREGA I RISULTATI DELLE CIRCOSCRIZIONI NEL RISULTATO NAZIONALE
+pub fn aggregate_district_results(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    election_name: String
+) -> ProgramResult {
+
+    let account_info_iter = &mut accounts.iter();
+
+    let initializer = next_account_info(account_info_iter)?;
+    let payer = next_account_info(account_info_iter)?;
+    let election_pda_account = next_account_info(account_info_iter)?;
+    let result_pda_account = next_account_info(account_info_iter)?;
+    let system_program = next_account_info(account_info_iter)?;
+    let candidate_list_pda_account = next_account_info(account_info_iter)?;
+
+    let (election_pda, _election_bump_seed) = Pubkey::find_program_address(
+        &[program_id.as_ref(), election_name.as_bytes()],
+        program_id
+    );
+    let (result_pda, _result_bump_seed) = Pubkey::find_program_address(
+        &[program_id.as_ref(), election_name.as_bytes(), "result".as_bytes()],
+        program_id
+    );
+    let (candidate_list_pda, _candidate_list_bump_seed) = Pubkey::find_program_address(
+        &[program_id.as_ref(), election_name.as_bytes(), "candidate-list".as_bytes()],
+        program_id
+    );
+    if election_pda != *election_pda_account.key || result_pda != *result_pda_account.key || candidate_list_pda != *candidate_list_pda_account.key {
+        msg!("Invalid seeds for PDA");
+        return Err(ProgramError::InvalidSeeds)
+    }
+
+    if election_pda_account.owner != program_id || candidate_list_pda_account.owner != program_id {
+        return Err(ProgramError::IllegalOwner)
+    }
+
+    let election_data = load_account::<ElectionAccountState>(election_pda_account)?;
+    check_tally_allowed(initializer, &election_data)?;
+
+    let mut vote_counts: BTreeMap<u32, RankedEntry> = BTreeMap::new();
+    let mut headcount: BTreeMap<String, i64> = BTreeMap::new();
+    let mut district_turnout: BTreeMap<String, i64> = BTreeMap::new();
+    let mut aggregated_districts: HashSet<Pubkey> = HashSet::new();
+    let mut total_number_of_votes: i64 = 0;
+    let mut total_weight: i64 = 0;
+
+    //SOMMA I RISULTATI DI OGNI CIRCOSCRIZIONE (COPPIE CIRCOSCRIZIONE, RISULTATI)
+    while let Ok(district_pda_account) = next_account_info(account_info_iter) {
+        let district_result_pda_account = next_account_info(account_info_iter)?;
+
+        let district_data = load_account::<DistrictState>(district_pda_account)?;
+        validate_district_account(program_id, district_pda_account, &election_name, &district_data.name)?;
+
+        let (district_result_pda, _district_result_bump_seed) = Pubkey::find_program_address(
+            &[program_id.as_ref(), election_name.as_bytes(), "district".as_bytes(), district_data.name.as_bytes(), "result".as_bytes()],
+            program_id
+        );
+        if district_result_pda != *district_result_pda_account.key {
+            msg!("Invalid seeds for district result {}", district_data.name);
+            return Err(ProgramError::InvalidSeeds)
+        }
+        if !aggregated_districts.insert(*district_pda_account.key) {
+            msg!("District {} passed twice", district_data.name);
+            return Err(ProgramError::InvalidArgument)
+        }
+        let district_result = load_account::<ResultState>(district_result_pda_account)?;
+
+        //I RISULTATI DELLA CIRCOSCRIZIONE DEVONO ESSERE AGGIORNATI
+        if district_result.number_of_votes != district_data.number_of_votes {
+            msg!("District {} has not been counted", district_data.name);
+            return Err(ProgramError::InvalidAccountData)
+        }
+
+        for entry in district_result.ranking {
+            vote_counts.entry(entry.index).or_insert(RankedEntry { votes: 0, ..entry.clone() }).votes += entry.votes;
+        }
+        for (candidate_info, voters) in district_result.headcount {
+            *headcount.entry(candidate_info).or_insert(0) += voters;
+        }
+        msg!("Affluenza circoscrizione {}: {} voti", district_data.name, district_result.number_of_votes);
+        total_number_of_votes += district_result.number_of_votes;
+        total_weight += district_result.total_weight;
+        district_turnout.insert(district_data.name, district_result.number_of_votes);
+    }
+
+    //TUTTE LE CIRCOSCRIZIONI DEVONO ESSERE AGGREGATE
+    if aggregated_districts.len() != election_data.number_of_districts as usize {
+        msg!("Expected {} districts, got {}", election_data.number_of_districts, aggregated_districts.len());
+        return Err(ProgramError::NotEnoughAccountKeys)
+    }
+
+    msg!("Risultati delle {}", election_name);
+    let mut result_data = ResultState {
+        district_turnout,
+        ..Default::default()
+    };
+
+    add_number_of_votes(&mut result_data, total_number_of_votes, total_weight);
+    add_headcount_results(&mut result_data, &headcount);
+    sort_and_add_results(&mut result_data, vote_counts.into_values().collect());
+    //LO SPAREGGIO SI APPLICA AL RISULTATO NAZIONALE COME NELLO SCRUTINIO SENZA CIRCOSCRIZIONI
+    break_ties(&mut result_data, &election_data, candidate_list_pda_account)?;
+    evaluate_outcome(&mut result_data, &election_data)?;
+
+    //RENDE IL RISULTATO NAZIONALE DEFINITIVO
+    finalize_tally(initializer, election_pda_account, &mut result_data)?;
+    save_with_realloc(&result_data, result_pda_account, payer, system_program)
+}
@@ -6,14 +6,16 @@ use solana_program::{
     account_info::{next_account_info, AccountInfo},
     system_instruction,
     program_error::ProgramError,
-    sysvar::{rent::Rent, Sysvar},
+    sysvar::{rent::Rent, clock::Clock, Sysvar},
     program::invoke_signed,
-    borsh0_10::try_from_slice_unchecked,
+    program::{set_return_data, MAX_RETURN_DATA},
 };
 
-use crate::state::election_account_state::ElectionAccountState;
-use crate::candidate_list_manager_account::generate_candidate_list_account;
-use crate::pda_management::result_manager_account::generate_result_account;
+use crate::state::{election_account_state::{ElectionAccountState, VotingMode, TieBreakPolicy, ElectionStatus, Quorum, MajorityRule}, result_state::ResultState, provisional_results_state::ProvisionalResultsState, candidate_state::CandidateState};
+use crate::candidate_list_manager_account::{generate_candidate_list_account, retrieve_candidate_list, add_finalist_to_candidate_list};
+use crate::pda_management::{candidate_manager_account::{candidate_address, create_candidate_account, intialize_candidate_account}, result_manager_account::{generate_result_account, retrieve_runoff_finalists}, shard_manager_account::merge_shard_counts, randomness_manager_account::MIN_TRUSTEES, voter_manager_accout::BALLOT_REVEAL_WINDOW_SECONDS};
+use crate::pda_management::vote_counters_manager_account::{generate_vote_counters_account, add_candidate_counter, merge_vote_counters};
+use crate::utilities::{token_account_utilities::unpack_token_account, election_account_utilities::{is_election_closed, is_election_started, is_retention_elapsed}, account_size_utilities::save_with_realloc, account_state_utilities::{load_account, save_account, pack_account}};
 use borsh::BorshSerialize;
 
 pub fn add_election_account(
@@ -29,26 +31,32 @@ pub fn add_election_account(
 
     let electione_name = name.clone();
     let election_name_for_result = name.clone();
+    let election_name_for_counters = name.clone();
 
     //CREA ITERATORE SU ACCOUNTS
     let account_info_iter = &mut accounts.iter();
 
     //RECUPERA ACCOUNT FORNITI DA ACCOUNT
     let initializer = next_account_info(account_info_iter)?;
+    let payer = next_account_info(account_info_iter)?;
     let system_program = next_account_info(account_info_iter)?;
     let election_pda_account = next_account_info(account_info_iter)?;
+
+    //CHI NON PAGA DEVE COMUNQUE FIRMARE
+    if !initializer.is_signer {
+        return Err(ProgramError::MissingRequiredSignature)
+    }
     //DERIVA PDA
     let (election_pda, election_bump_seed) = Pubkey::find_program_address(
-        &[program_id.as_ref(), name.as_bytes().as_ref()],
+        &[program_id.as_ref(), name.as_bytes()],
          program_id
         );    
     
-    //CALCOLA DIMENSIONE DELL'ACCOUNT
-    let account_len: usize = 1 +
-     (4 * name.len()) + 
-     (4 * formatted_start_date.len()) +
-     (4 * formatted_end_date.len()) +
-    10000;
+    //STATO INIZIALE DELL'ELEZIONE
+    let election_data = new_election_account_state(initializer.key, name.clone(), formatted_start_date, formatted_end_date);
+
+    //CALCOLA DIMENSIONE DELL'ACCOUNT DAL LAYOUT BORSH, CRESCE CON REALLOC
+    let account_len: usize = pack_account(&election_data)?.len();
 
     //CALCOLA IL COSTO DI RENT
     let rent = Rent::get()?;
@@ -57,20 +65,21 @@ pub fn add_election_account(
     //CREA L'ACCOUNT 
     invoke_signed(
         &system_instruction::create_account(
-            initializer.key, 
+            payer.key, 
             election_pda_account.key, 
             rent_lamports,
             account_len.try_into().unwrap(), 
             program_id
         ), 
-        &[initializer.clone(),election_pda_account.clone(),system_program.clone()], 
-        &[&[program_id.as_ref(),name.as_bytes().as_ref(), &[election_bump_seed]]]
+        &[payer.clone(), election_pda_account.clone(),system_program.clone()], 
+        &[&[program_id.as_ref(),name.as_bytes(), &[election_bump_seed]]]
     )?;
 
     msg!("PDA Created: {}",election_pda);
 
     //INIZIALIZZA L'ACCOUNT 
-    let is_election_created = initialize_election_account(election_pda_account, name, formatted_start_date, formatted_end_date);
+    msg!("Serializing account");
+    let is_election_created = save_account(&election_data, election_pda_account);
        
     if is_election_created.is_ok(){
 
@@ -81,111 +90,769 @@ pub fn add_election_account(
             let is_result_account_created = generate_result_account(program_id, accounts, election_name_for_result);
 
             if is_result_account_created.is_ok() {
-                 Ok(())
+                 generate_vote_counters_account(program_id, accounts, election_name_for_counters)
             }
             else {
-                return Err(ProgramError::IncorrectProgramId);
+                Err(ProgramError::IncorrectProgramId)
             }
         }
         else {
-            return Err(ProgramError::IncorrectProgramId)
+            Err(ProgramError::IncorrectProgramId)
         }
     } else {
-        return Err(ProgramError::AccountBorrowFailed)
+        Err(ProgramError::AccountBorrowFailed)
     }
 
     
 }
 
-pub fn initialize_election_account(
-    pda_account: &AccountInfo,
+//CREA IL BALLOTTAGGIO TRA I DUE CANDIDATI PIU' VOTATI DEL PRIMO TURNO
+//I votanti restano identificati dallo stesso numero di tessera elettorale del primo turno.
+//Dopo gli account dell'elezione e del primo turno, per ogni finalista nell'ordine della classifica:
+//il suo account candidato del primo turno e il nuovo account candidato del ballottaggio,
+//derivato dal PDA del ballottaggio con l'indice del finalista (0 e 1) come per ogni candidato.
+pub fn add_runoff_election(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    first_round_name: String,
     name: String,
-    start_date: String,
-    end_date: String
+    start_date: NaiveDateTime,
+    end_date: NaiveDateTime
 ) -> ProgramResult {
-    msg!("Unpacking vote account");
-    let mut account_data = try_from_slice_unchecked::<ElectionAccountState>(&pda_account.data.borrow()).unwrap();
 
-    account_data.name = name;
-    account_data.start_date = start_date;
-    account_data.end_date = end_date;
-    account_data.is_initialized = true;
-    account_data.is_active = false;
-    account_data.number_of_votes = 0;
+    //CREA ITERATORE SU ACCOUNTS
+    let account_info_iter = &mut accounts.iter();
 
-    msg!("Serializing account");
-    account_data.serialize(&mut &mut pda_account.data.borrow_mut()[..])?;
-    msg!("Account serialized");
+    //RECUPERA ACCOUNT FORNITI DAL CLIENT
+    let initializer = next_account_info(account_info_iter)?;
+    let payer = next_account_info(account_info_iter)?;
+    let system_program = next_account_info(account_info_iter)?;
+    let runoff_election_pda_account = next_account_info(account_info_iter)?;
+    let runoff_candidate_list_pda_account = next_account_info(account_info_iter)?;
+    let _runoff_result_pda_account = next_account_info(account_info_iter)?;
+    let runoff_vote_counters_pda_account = next_account_info(account_info_iter)?;
+    let first_round_election_pda_account = next_account_info(account_info_iter)?;
+    let first_round_candidate_list_pda_account = next_account_info(account_info_iter)?;
+    let first_round_result_pda_account = next_account_info(account_info_iter)?;
+
+    //DERIVA I PDA DEL PRIMO TURNO
+    let (first_round_election_pda, _first_round_election_bump_seed) = Pubkey::find_program_address(
+        &[program_id.as_ref(), first_round_name.as_bytes()],
+        program_id
+    );
+    let (first_round_candidate_list_pda, _first_round_candidate_list_bump_seed) = Pubkey::find_program_address(
+        &[program_id.as_ref(), first_round_name.as_bytes(), "candidate-list".as_bytes()],
+        program_id
+    );
+    let (first_round_result_pda, _first_round_result_bump_seed) = Pubkey::find_program_address(
+        &[program_id.as_ref(), first_round_name.as_bytes(), "result".as_bytes()],
+        program_id
+    );
+
+    //VALIDAZIONE DEGLI ACCOUNT DEL PRIMO TURNO
+    if first_round_election_pda != *first_round_election_pda_account.key
+        || first_round_candidate_list_pda != *first_round_candidate_list_pda_account.key
+        || first_round_result_pda != *first_round_result_pda_account.key {
+        msg!("Invalid seeds for first round accounts");
+        return Err(ProgramError::InvalidSeeds)
+    }
+    if first_round_election_pda_account.owner != program_id || first_round_result_pda_account.owner != program_id {
+        return Err(ProgramError::IllegalOwner)
+    }
+
+    let first_round_data = load_account::<ElectionAccountState>(first_round_election_pda_account)?;
+
+    //SOLO L'AUTORITA' DEL PRIMO TURNO PUO' INDIRE IL BALLOTTAGGIO
+    if !initializer.is_signer || first_round_data.authority != *initializer.key {
+        msg!("Only the election authority can create a runoff");
+        return Err(ProgramError::MissingRequiredSignature)
+    }
+    if first_round_data.round > 1 {
+        msg!("{} is already a runoff", first_round_name);
+        return Err(ProgramError::InvalidAccountData)
+    }
+
+    //RECUPERA I DUE FINALISTI E I LORO ACCOUNT DAL PRIMO TURNO
+    let (runoff_election_pda, _runoff_election_bump_seed) = Pubkey::find_program_address(
+        &[program_id.as_ref(), name.as_bytes()],
+        program_id
+    );
+    let candidate_list = retrieve_candidate_list(first_round_candidate_list_pda_account)?;
+    let mut finalists = Vec::new();
+    for (index, finalist) in (0u32..).zip(retrieve_runoff_finalists(first_round_result_pda_account)?) {
+        let candidate = candidate_list.iter().find(|candidate| candidate.name == finalist).ok_or(ProgramError::InvalidAccountData)?;
+        let first_round_candidate_pda_account = next_account_info(account_info_iter)?;
+        let candidate_pda_account = next_account_info(account_info_iter)?;
+
+        if candidate.address != *first_round_candidate_pda_account.key {
+            msg!("Invalid first round account for {}", finalist);
+            return Err(ProgramError::InvalidSeeds)
+        }
+        if first_round_candidate_pda_account.owner != program_id {
+            return Err(ProgramError::IllegalOwner)
+        }
+        let (candidate_pda, candidate_bump_seed) = candidate_address(program_id, &runoff_election_pda, index);
+        if candidate_pda != *candidate_pda_account.key {
+            msg!("Invalid seeds for candidate {}", index);
+            return Err(ProgramError::InvalidSeeds)
+        }
+
+        let candidate_data = load_account::<CandidateState>(first_round_candidate_pda_account)?;
+        finalists.push((finalist, candidate.address, candidate_pda_account, index, candidate_bump_seed, candidate_data));
+    }
+
+    //CREA ELEZIONE, LISTA CANDIDATI E RISULTATI DEL BALLOTTAGGIO
+    add_election_account(program_id, accounts, name, start_date, end_date)?;
+    link_runoff_to_first_round(runoff_election_pda_account, payer, system_program, &first_round_data)?;
+
+    //INSERISCE I FINALISTI NEL BALLOTTAGGIO CON UN ACCOUNT CANDIDATO DEL BALLOTTAGGIO
+    for (candidate_info, first_round_candidate_address, candidate_pda_account, index, candidate_bump_seed, candidate_data) in finalists {
+        msg!("Finalist: {}", candidate_info);
+        create_candidate_account(program_id, candidate_pda_account, payer, system_program, &runoff_election_pda, index, candidate_bump_seed, &candidate_data.first_name, &candidate_data.last_name)?;
+        intialize_candidate_account(candidate_pda_account, candidate_data.first_name, candidate_data.last_name)?;
+        add_finalist_to_candidate_list(runoff_candidate_list_pda_account, first_round_candidate_list_pda_account, payer, system_program, candidate_info, first_round_candidate_address, *candidate_pda_account.key)?;
+        add_candidate_to_election(runoff_election_pda_account, runoff_vote_counters_pda_account, payer, system_program, *candidate_pda_account.key)?;
+    }
 
     Ok(())
 }
 
-pub fn increment_vote_counter (
-    election_pda_account: &AccountInfo
+pub fn link_runoff_to_first_round<'a>(
+    runoff_election_pda_account: &AccountInfo<'a>,
+    payer: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>,
+    first_round_data: &ElectionAccountState
 ) -> ProgramResult {
-    msg!("Unpacking vote account...");
-    let mut account_data: ElectionAccountState = try_from_slice_unchecked::<ElectionAccountState>(&election_pda_account.data.borrow()).unwrap();
+    let mut account_data = load_account::<ElectionAccountState>(runoff_election_pda_account)?;
 
-    account_data.number_of_votes +=1;
+    account_data.round = 2;
+    account_data.first_round = first_round_data.name.clone();
 
-    msg!("Serializing account");
-    account_data.serialize(&mut &mut election_pda_account.data.borrow_mut()[..])?;
-    msg!("Vote account serialized");
+    //IL VOTO SU TOKEN RICHIEDE UN VAULT DEDICATO, DA CONFIGURARE CON SetTokenVoting
+    if first_round_data.voting_mode != VotingMode::TokenWeighted {
+        account_data.voting_mode = first_round_data.voting_mode;
+        account_data.voice_credits = first_round_data.voice_credits;
+    }
+    //IL BALLOTTAGGIO HA GLI STESSI GARANTI MA UN NUOVO SEME CASUALE
+    account_data.tie_break = first_round_data.tie_break;
+    account_data.trustees = first_round_data.trustees.clone();
+    //QUORUM E MAGGIORANZA DEL PRIMO TURNO, CALCOLATI SULLE STESSE LISTE ELETTORALI
+    account_data.quorum = first_round_data.quorum;
+    account_data.majority_rule = first_round_data.majority_rule;
+    account_data.roll_size = first_round_data.roll_size;
+    account_data.roll_weight = first_round_data.roll_weight;
+
+    save_with_realloc(&account_data, runoff_election_pda_account, payer, system_program)
+}
+
+//IMPOSTA LA MODALITA' DI VOTO, PRIMA CHE INIZINO LE VOTAZIONI
+pub fn set_voting_mode(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    election_name: String,
+    voting_mode: VotingMode
+) -> ProgramResult {
+
+    let account_info_iter = &mut accounts.iter();
+
+    let initializer = next_account_info(account_info_iter)?;
+    let election_pda_account = next_account_info(account_info_iter)?;
+
+    let (election_pda, _election_bump_seed) = Pubkey::find_program_address(
+        &[program_id.as_ref(), election_name.as_bytes()],
+        program_id
+    );
+    if election_pda != *election_pda_account.key {
+        msg!("Invalid seeds for PDA");
+        return Err(ProgramError::InvalidSeeds)
+    }
+    if election_pda_account.owner != program_id {
+        return Err(ProgramError::IllegalOwner)
+    }
+
+    let mut account_data = load_account::<ElectionAccountState>(election_pda_account)?;
+
+    if !initializer.is_signer || account_data.authority != *initializer.key {
+        msg!("Only the election authority can change the voting mode");
+        return Err(ProgramError::MissingRequiredSignature)
+    }
+    if voting_started(&account_data)? {
+        msg!("Voting mode cannot change after voting has started");
+        return Err(ProgramError::InvalidAccountData)
+    }
+    if voting_mode == VotingMode::TokenWeighted {
+        msg!("Token voting needs a mint and a vault, use SetTokenVoting");
+        return Err(ProgramError::InvalidArgument)
+    }
+    if voting_mode == VotingMode::Quadratic {
+        msg!("Quadratic voting needs a credit budget, use SetQuadraticVoting");
+        return Err(ProgramError::InvalidArgument)
+    }
+
+    msg!("Voting mode for {}: {:?}", election_name, voting_mode);
+    account_data.voting_mode = voting_mode;
+    save_account(&account_data, election_pda_account)?;
 
     Ok(())
 }
 
-pub fn add_candidate_to_election(
-    election_pda_account: &AccountInfo,
-    candidate_address: Pubkey
+//ABILITA IL VOTO PONDERATO SUL SALDO DI UN TOKEN SPL
+//I token di chi vota restano bloccati nel vault dell'elezione fino alla chiusura
+pub fn set_token_voting(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    election_name: String,
+    mint: Pubkey
 ) -> ProgramResult {
-    msg!("Unpacking vote account...");
-    let mut account_data: ElectionAccountState = try_from_slice_unchecked::<ElectionAccountState>(&election_pda_account.data.borrow()).unwrap();
 
-    account_data.votes.insert(candidate_address, 0);
+    let account_info_iter = &mut accounts.iter();
 
-    msg!("Aggiunto Candidato all'Elezione");
+    let initializer = next_account_info(account_info_iter)?;
+    let election_pda_account = next_account_info(account_info_iter)?;
+    let vault_token_account = next_account_info(account_info_iter)?;
+
+    let (election_pda, _election_bump_seed) = Pubkey::find_program_address(
+        &[program_id.as_ref(), election_name.as_bytes()],
+        program_id
+    );
+    let (vault_pda, _vault_bump_seed) = Pubkey::find_program_address(
+        &[program_id.as_ref(), election_name.as_bytes(), "vault".as_bytes()],
+        program_id
+    );
+    if election_pda != *election_pda_account.key {
+        msg!("Invalid seeds for PDA");
+        return Err(ProgramError::InvalidSeeds)
+    }
+    if election_pda_account.owner != program_id {
+        return Err(ProgramError::IllegalOwner)
+    }
 
-    msg!("Serializing account");
-    account_data.serialize(&mut &mut election_pda_account.data.borrow_mut()[..])?;
-    msg!("Vote account serialized");
+    let mut account_data = load_account::<ElectionAccountState>(election_pda_account)?;
+
+    if !initializer.is_signer || account_data.authority != *initializer.key {
+        msg!("Only the election authority can configure token voting");
+        return Err(ProgramError::MissingRequiredSignature)
+    }
+    if voting_started(&account_data)? || account_data.number_of_districts > 0 || account_data.embargo {
+        msg!("Token voting must be configured before voting, without districts or embargo");
+        return Err(ProgramError::InvalidAccountData)
+    }
+
+    //IL VAULT DEVE CONTENERE IL TOKEN DELL'ELEZIONE ED ESSERE DEL PDA
+    let vault = unpack_token_account(vault_token_account)?;
+    if vault.mint != mint || vault.owner != vault_pda {
+        msg!("Vault must be a {} token account owned by {}", mint, vault_pda);
+        return Err(ProgramError::InvalidAccountData)
+    }
+
+    msg!("Token voting for {} with mint {}", election_name, mint);
+    account_data.voting_mode = VotingMode::TokenWeighted;
+    account_data.mint = mint;
+    account_data.token_vault = *vault_token_account.key;
+    save_account(&account_data, election_pda_account)?;
 
     Ok(())
 }
 
-pub fn add_vote(
-    pda_account: &AccountInfo,
-    candidate_address: Pubkey,
+//ABILITA IL VOTO QUADRATICO: OGNI VOTANTE RICEVE voice_credits CREDITI
+pub fn set_quadratic_voting(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    election_name: String,
+    voice_credits: u64
+) -> ProgramResult {
+
+    let account_info_iter = &mut accounts.iter();
+
+    let initializer = next_account_info(account_info_iter)?;
+    let election_pda_account = next_account_info(account_info_iter)?;
+
+    let (election_pda, _election_bump_seed) = Pubkey::find_program_address(
+        &[program_id.as_ref(), election_name.as_bytes()],
+        program_id
+    );
+    if election_pda != *election_pda_account.key {
+        msg!("Invalid seeds for PDA");
+        return Err(ProgramError::InvalidSeeds)
+    }
+    if election_pda_account.owner != program_id {
+        return Err(ProgramError::IllegalOwner)
+    }
+
+    let mut account_data = load_account::<ElectionAccountState>(election_pda_account)?;
+
+    if !initializer.is_signer || account_data.authority != *initializer.key {
+        msg!("Only the election authority can configure quadratic voting");
+        return Err(ProgramError::MissingRequiredSignature)
+    }
+    if voting_started(&account_data)? || account_data.number_of_districts > 0 || account_data.embargo {
+        msg!("Quadratic voting must be configured before voting, without districts or embargo");
+        return Err(ProgramError::InvalidAccountData)
+    }
+    if voice_credits == 0 {
+        msg!("Voice credits must be positive");
+        return Err(ProgramError::InvalidArgument)
+    }
+
+    msg!("Quadratic voting for {} with {} credits per voter", election_name, voice_credits);
+    account_data.voting_mode = VotingMode::Quadratic;
+    account_data.voice_credits = voice_credits;
+    save_account(&account_data, election_pda_account)?;
+
+    Ok(())
+}
 
+//IMPOSTA IL TEMA DELL'ELEZIONE, USATO PER LE DELEGHE PER TEMA
+pub fn set_election_topic(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    election_name: String,
+    topic: String
 ) -> ProgramResult {
-    msg!("Unpacking vote account...");
-    let mut account_data: ElectionAccountState = try_from_slice_unchecked::<ElectionAccountState>(&pda_account.data.borrow()).unwrap();
 
-    msg!("Adding new vote");
+    let account_info_iter = &mut accounts.iter();
+
+    let initializer = next_account_info(account_info_iter)?;
+    let payer = next_account_info(account_info_iter)?;
+    let election_pda_account = next_account_info(account_info_iter)?;
+    let system_program = next_account_info(account_info_iter)?;
+
+    let (election_pda, _election_bump_seed) = Pubkey::find_program_address(
+        &[program_id.as_ref(), election_name.as_bytes()],
+        program_id
+    );
+    if election_pda != *election_pda_account.key {
+        msg!("Invalid seeds for PDA");
+        return Err(ProgramError::InvalidSeeds)
+    }
+    if election_pda_account.owner != program_id {
+        return Err(ProgramError::IllegalOwner)
+    }
+
+    let mut account_data = load_account::<ElectionAccountState>(election_pda_account)?;
+
+    if !initializer.is_signer || account_data.authority != *initializer.key {
+        msg!("Only the election authority can set the topic");
+        return Err(ProgramError::MissingRequiredSignature)
+    }
+    if voting_started(&account_data)? {
+        msg!("Topic cannot change after voting has started");
+        return Err(ProgramError::InvalidAccountData)
+    }
+
+    msg!("Topic for {}: {}", election_name, topic);
+    account_data.topic = topic;
+    save_with_realloc(&account_data, election_pda_account, payer, system_program)?;
+
+    Ok(())
+}
+
+//IMPOSTA LA REGOLA DI SPAREGGIO, PRIMA CHE INIZINO LE VOTAZIONI
+//Il sorteggio usa il seme casuale prodotto dai garanti dell'elezione
+pub fn set_tie_break_policy(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    election_name: String,
+    tie_break: TieBreakPolicy
+) -> ProgramResult {
+
+    let account_info_iter = &mut accounts.iter();
+
+    let initializer = next_account_info(account_info_iter)?;
+    let election_pda_account = next_account_info(account_info_iter)?;
 
-    if let Some(value) = account_data.votes.get_mut(&candidate_address) {
-        *value += 1;
+    let (election_pda, _election_bump_seed) = Pubkey::find_program_address(
+        &[program_id.as_ref(), election_name.as_bytes()],
+        program_id
+    );
+    if election_pda != *election_pda_account.key {
+        msg!("Invalid seeds for PDA");
+        return Err(ProgramError::InvalidSeeds)
     }
+    if election_pda_account.owner != program_id {
+        return Err(ProgramError::IllegalOwner)
+    }
+
+    let mut account_data = load_account::<ElectionAccountState>(election_pda_account)?;
+
+    if !initializer.is_signer || account_data.authority != *initializer.key {
+        msg!("Only the election authority can set the tie-break policy");
+        return Err(ProgramError::MissingRequiredSignature)
+    }
+    if voting_started(&account_data)? {
+        msg!("Tie-break policy cannot change after voting has started");
+        return Err(ProgramError::InvalidAccountData)
+    }
+
+    //IL SORTEGGIO RICHIEDE GARANTI INDIPENDENTI DALL'AUTORITA'
+    if tie_break == TieBreakPolicy::Lot && account_data.trustees.len() < MIN_TRUSTEES {
+        msg!("Lot requires at least {} trustees", MIN_TRUSTEES);
+        return Err(ProgramError::InvalidArgument)
+    }
+
+    msg!("Tie-break policy for {}: {:?}", election_name, tie_break);
+    account_data.tie_break = tie_break;
+    save_account(&account_data, election_pda_account)?;
+
+    Ok(())
+}
+
+//IMPOSTA QUORUM E MAGGIORANZA RICHIESTA, PRIMA CHE INIZINO LE VOTAZIONI
+pub fn set_quorum_rules(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    election_name: String,
+    quorum: Quorum,
+    majority_rule: MajorityRule
+) -> ProgramResult {
+
+    let account_info_iter = &mut accounts.iter();
+
+    let initializer = next_account_info(account_info_iter)?;
+    let payer = next_account_info(account_info_iter)?;
+    let election_pda_account = next_account_info(account_info_iter)?;
+    let system_program = next_account_info(account_info_iter)?;
+
+    let (election_pda, _election_bump_seed) = Pubkey::find_program_address(
+        &[program_id.as_ref(), election_name.as_bytes()],
+        program_id
+    );
+    if election_pda != *election_pda_account.key {
+        msg!("Invalid seeds for PDA");
+        return Err(ProgramError::InvalidSeeds)
+    }
+    if election_pda_account.owner != program_id {
+        return Err(ProgramError::IllegalOwner)
+    }
+
+    let mut account_data = load_account::<ElectionAccountState>(election_pda_account)?;
+
+    if !initializer.is_signer || account_data.authority != *initializer.key {
+        msg!("Only the election authority can set the quorum");
+        return Err(ProgramError::MissingRequiredSignature)
+    }
+    if voting_started(&account_data)? {
+        msg!("Quorum cannot change after voting has started");
+        return Err(ProgramError::InvalidAccountData)
+    }
+    match quorum {
+        Quorum::Absolute(0) => {
+            msg!("Quorum must be positive");
+            return Err(ProgramError::InvalidArgument)
+        }
+        Quorum::RollShare(share) if share == 0 || share > 10_000 => {
+            msg!("Quorum share must be between 1 and 10000 basis points");
+            return Err(ProgramError::InvalidArgument)
+        }
+        //LA QUOTA SI CALCOLA SULLE LISTE ELETTORALI: SENZA ISCRITTI IL QUORUM SAREBBE ZERO
+        Quorum::RollShare(_) if account_data.roll_size <= 0 => {
+            msg!("Election {} has no electoral roll", election_name);
+            return Err(ProgramError::InvalidArgument)
+        }
+        _ => {}
+    }
+
+    msg!("Quorum for {}: {:?}, majority {:?}", election_name, quorum, majority_rule);
+    account_data.quorum = quorum;
+    account_data.majority_rule = majority_rule;
+    save_with_realloc(&account_data, election_pda_account, payer, system_program)?;
+
+    Ok(())
+}
+
+//ATTIVA O DISATTIVA L'EMBARGO SUI CONTEGGI PER CANDIDATO, PRIMA CHE INIZINO LE VOTAZIONI
+//Sotto embargo si vota con schede sigillate: durante il voto la chain registra solo l'affluenza
+//e i contatori per candidato si riempiono con le rivelazioni dopo la chiusura
+pub fn set_embargo(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    election_name: String,
+    embargo: bool
+) -> ProgramResult {
+
+    let account_info_iter = &mut accounts.iter();
+
+    let initializer = next_account_info(account_info_iter)?;
+    let election_pda_account = next_account_info(account_info_iter)?;
+
+    let (election_pda, _election_bump_seed) = Pubkey::find_program_address(
+        &[program_id.as_ref(), election_name.as_bytes()],
+        program_id
+    );
+    if election_pda != *election_pda_account.key {
+        msg!("Invalid seeds for PDA");
+        return Err(ProgramError::InvalidSeeds)
+    }
+    if election_pda_account.owner != program_id {
+        return Err(ProgramError::IllegalOwner)
+    }
+
+    let mut account_data = load_account::<ElectionAccountState>(election_pda_account)?;
+
+    if !initializer.is_signer || account_data.authority != *initializer.key {
+        msg!("Only the election authority can set the embargo");
+        return Err(ProgramError::MissingRequiredSignature)
+    }
+    if voting_started(&account_data)? {
+        msg!("Embargo cannot change after voting has started");
+        return Err(ProgramError::InvalidAccountData)
+    }
+    //LE SCHEDE SIGILLATE VALGONO SOLO PER IL VOTO PER TESTA O PONDERATO
+    if embargo && !matches!(account_data.voting_mode, VotingMode::Standard | VotingMode::Weighted) {
+        msg!("Embargo is not supported for {:?} voting", account_data.voting_mode);
+        return Err(ProgramError::InvalidArgument)
+    }
+
+    msg!("Embargo for {}: {}", election_name, embargo);
+    account_data.embargo = embargo;
+    save_account(&account_data, election_pda_account)?;
+
+    Ok(())
+}
+
+//VISTA PROVVISORIA IN SOLA LETTURA: AFFLUENZA SEMPRE, CONTEGGI PER CANDIDATO SOLO SENZA EMBARGO O A ELEZIONE CHIUSA
+//L'affluenza e' nell'intestazione dell'account contatori, separata dai contatori per candidato:
+//sotto embargo questi restano vuoti fino alla rivelazione delle schede sigillate.
+pub fn show_provisional_results(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    election_name: String
+) -> ProgramResult {
+
+    let account_info_iter = &mut accounts.iter();
+
+    let election_pda_account = next_account_info(account_info_iter)?;
+    let vote_counters_pda_account = next_account_info(account_info_iter)?;
+
+    let (election_pda, _election_bump_seed) = Pubkey::find_program_address(
+        &[program_id.as_ref(), election_name.as_bytes()],
+        program_id
+    );
+    if election_pda != *election_pda_account.key {
+        msg!("Invalid seeds for PDA");
+        return Err(ProgramError::InvalidSeeds)
+    }
+    if election_pda_account.owner != program_id {
+        return Err(ProgramError::IllegalOwner)
+    }
+
+    let mut account_data = load_account::<ElectionAccountState>(election_pda_account)?;
+    //I TOTALI SONO NELL'ACCOUNT CONTATORI E, SE ATTIVI, NEI CONTATORI DISTRIBUITI PASSATI DOPO
+    merge_vote_counters(program_id, &mut account_data, vote_counters_pda_account)?;
+    merge_shard_counts(program_id, &mut account_data, account_info_iter)?;
+
+    let votes = if account_data.embargo && !is_election_closed(&account_data.end_date)? {
+        None
+    } else {
+        //IN ORDINE DI INDICE DEL CANDIDATO
+        Some(account_data.candidates.iter().copied().zip(account_data.votes).collect())
+    };
+
+    msg!("Affluenza {}: {} votanti", election_name, account_data.number_of_votes);
+    if votes.is_none() {
+        msg!("Conteggi per candidato sotto embargo fino a {}", account_data.end_date);
+    }
+
+    let provisional_results = ProvisionalResultsState {
+        number_of_votes: account_data.number_of_votes,
+        total_weight: account_data.total_weight,
+        roll_size: account_data.roll_size,
+        votes,
+    };
+    let return_data = provisional_results.try_to_vec()?;
+    if return_data.len() > MAX_RETURN_DATA {
+        msg!("Too many candidates for the provisional view");
+        return Err(ProgramError::InvalidAccountData)
+    }
+    set_return_data(&return_data);
+
+    Ok(())
+}
+
+//AUTORIZZA UN RICONTEGGIO DI UN'ELEZIONE GIA' SCRUTINATA
+//I risultati tornano scrivibili finche' lo scrutinio non viene chiuso di nuovo
+pub fn recount_votes(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    election_name: String
+) -> ProgramResult {
+
+    let account_info_iter = &mut accounts.iter();
+
+    let initializer = next_account_info(account_info_iter)?;
+    let election_pda_account = next_account_info(account_info_iter)?;
+
+    let (election_pda, _election_bump_seed) = Pubkey::find_program_address(
+        &[program_id.as_ref(), election_name.as_bytes()],
+        program_id
+    );
+    if election_pda != *election_pda_account.key {
+        msg!("Invalid seeds for PDA");
+        return Err(ProgramError::InvalidSeeds)
+    }
+    if election_pda_account.owner != program_id {
+        return Err(ProgramError::IllegalOwner)
+    }
+
+    let mut account_data = load_account::<ElectionAccountState>(election_pda_account)?;
+
+    if !initializer.is_signer || account_data.authority != *initializer.key {
+        msg!("Only the election authority can order a recount");
+        return Err(ProgramError::MissingRequiredSignature)
+    }
+    if account_data.status != ElectionStatus::Tallied {
+        msg!("Election {} has not been tallied", election_name);
+        return Err(ProgramError::InvalidAccountData)
+    }
+
+    account_data.status = ElectionStatus::Recounting;
+    account_data.recounts += 1;
+    msg!("Riconteggio n. {} per {}", account_data.recounts, election_name);
+    save_account(&account_data, election_pda_account)?;
+
+    Ok(())
+}
+
+//VERIFICA CHE LO SCRUTINIO SIA AMMESSO: SOLO L'AUTORITA', A ELEZIONE CHIUSA E NON ANCORA SCRUTINATA
+pub fn check_tally_allowed(
+    initializer: &AccountInfo,
+    election_data: &ElectionAccountState
+) -> ProgramResult {
+    if !initializer.is_signer || election_data.authority != *initializer.key {
+        msg!("Only the election authority can tally the votes");
+        return Err(ProgramError::MissingRequiredSignature)
+    }
+    if election_data.status == ElectionStatus::Tallied {
+        msg!("Election {} has already been tallied, order a recount first", election_data.name);
+        return Err(ProgramError::AccountAlreadyInitialized)
+    }
+    if election_data.status == ElectionStatus::Archived {
+        msg!("Election {} has been archived", election_data.name);
+        return Err(ProgramError::InvalidAccountData)
+    }
+    if !is_election_closed(&election_data.end_date)? {
+        msg!("Election {} is still open", election_data.name);
+        return Err(ProgramError::InvalidAccountData)
+    }
+    //SOTTO EMBARGO SI SCRUTINA SOLO DOPO LA FINESTRA DI RIVELAZIONE DELLE SCHEDE SIGILLATE
+    if election_data.embargo && !is_retention_elapsed(&election_data.end_date, BALLOT_REVEAL_WINDOW_SECONDS)? {
+        msg!("Sealed ballots of {} can still be revealed", election_data.name);
+        return Err(ProgramError::InvalidAccountData)
+    }
+
+    Ok(())
+}
+
+//CHIUDE LO SCRUTINIO: L'ELEZIONE DIVENTA Tallied E IL RISULTATO DEFINITIVO
+pub fn finalize_tally(
+    initializer: &AccountInfo,
+    election_pda_account: &AccountInfo,
+    result_data: &mut ResultState
+) -> ProgramResult {
+    let mut election_data = load_account::<ElectionAccountState>(election_pda_account)?;
+
+    election_data.status = ElectionStatus::Tallied;
+    result_data.is_final = true;
+    result_data.tallied_slot = Clock::get()?.slot;
+    result_data.tallied_by = *initializer.key;
+    result_data.recount = election_data.recounts;
+
+    msg!("Scrutinio definitivo allo slot {}", result_data.tallied_slot);
+    save_account(&election_data, election_pda_account)?;
+
+    Ok(())
+}
+
+//IL VOTO E' AMMESSO DALLA DATA DI INIZIO ALLA DATA DI FINE, SE L'ELEZIONE NON E' SCRUTINATA
+pub fn check_voting_open(
+    election_data: &ElectionAccountState
+) -> ProgramResult {
+    if election_data.status != ElectionStatus::Open {
+        msg!("Election {} has been tallied", election_data.name);
+        return Err(ProgramError::InvalidAccountData)
+    }
+    if !is_election_started(&election_data.start_date)? {
+        msg!("Election {} opens on {}", election_data.name, election_data.start_date);
+        return Err(ProgramError::InvalidAccountData)
+    }
+    //DOPO LA CHIUSURA NESSUN VOTO: NE' TRA LE PAGINE DELLO SCRUTINIO, NE' DOPO L'EMBARGO O IL SORTEGGIO
+    if is_election_closed(&election_data.end_date)? {
+        msg!("Election {} closed on {}", election_data.name, election_data.end_date);
+        return Err(ProgramError::InvalidAccountData)
+    }
+
+    Ok(())
+}
+
+//LE VOTAZIONI SONO INIZIATE: I VOTI SONO NEI CONTATORI E L'ACCOUNT ELEZIONE NON LI VEDE,
+//QUINDI LA CONFIGURAZIONE VIENE BLOCCATA ALLA DATA DI INIZIO O QUANDO SI ATTIVANO I CONTATORI DISTRIBUITI
+pub fn voting_started(
+    election_data: &ElectionAccountState
+) -> Result<bool, ProgramError> {
+    Ok(election_data.number_of_votes > 0 || election_data.number_of_shards > 0 || is_election_started(&election_data.start_date)?)
+}
+
+pub fn new_election_account_state(
+    authority: &Pubkey,
+    name: String,
+    start_date: String,
+    end_date: String
+) -> ElectionAccountState {
+    ElectionAccountState {
+        is_initialized: true,
+        name,
+        start_date,
+        end_date,
+        is_active: false,
+        number_of_votes: 0,
+        authority: *authority,
+        round: 1,
+        ..Default::default()
+    }
+}
+
+pub fn increment_vote_counter (
+    election_pda_account: &AccountInfo
+) -> ProgramResult {
+    msg!("Unpacking vote account...");
+    let mut account_data: ElectionAccountState = load_account::<ElectionAccountState>(election_pda_account)?;
+
     account_data.number_of_votes +=1;
 
     msg!("Serializing account");
-    account_data.serialize(&mut &mut pda_account.data.borrow_mut()[..])?;
+    save_account(&account_data, election_pda_account)?;
     msg!("Vote account serialized");
 
     Ok(())
 }
 
-pub fn get_percentage_of_votes (
-    election_pda_account: &AccountInfo,
-    candidate_pda_address: Pubkey
-) -> Result<f32,ProgramError> {
+pub fn add_candidate_to_election<'a>(
+    election_pda_account: &AccountInfo<'a>,
+    vote_counters_pda_account: &AccountInfo<'a>,
+    payer: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>,
+    candidate_address: Pubkey
+) -> ProgramResult {
+    msg!("Unpacking vote account...");
+    let mut account_data: ElectionAccountState = load_account::<ElectionAccountState>(election_pda_account)?;
 
-    let account_data: ElectionAccountState = try_from_slice_unchecked::<ElectionAccountState>(&election_pda_account.data.borrow()).unwrap();
-    
-    let votes_for_candidate = account_data.votes.get(&candidate_pda_address).unwrap();
-    let percentage = (100.0/account_data.number_of_votes as f32) * *votes_for_candidate as f32;
+    //L'ORDINE DI SCHEDA SI ESTRAE SU UNA LISTA CHIUSA
+    if !account_data.ballot_commitments.is_empty() {
+        msg!("Candidate list of {} is closed for the ballot order draw", account_data.name);
+        return Err(ProgramError::InvalidAccountData)
+    }
+
+    //IL CANDIDATO OCCUPA LA PROSSIMA POSIZIONE IN OGNI CONTATORE E NELL'ACCOUNT CONTATORI
+    add_candidate_counter(vote_counters_pda_account, payer, system_program, account_data.candidates.len())?;
+    account_data.push_candidate(candidate_address);
+
+    msg!("Aggiunto Candidato all'Elezione");
 
-    return Ok(percentage);
+    msg!("Serializing account");
+    save_with_realloc(&account_data, election_pda_account, payer, system_program)?;
+    msg!("Vote account serialized");
+
+    Ok(())
 }
 
 //OTTIENE IL NUMERO TOTALE DI VOTI
@@ -193,9 +860,9 @@ pub fn get_number_of_votes (
     election_pda_account: &AccountInfo,
 ) -> Result<i64,ProgramError> {
 
-    let account_data: ElectionAccountState = try_from_slice_unchecked::<ElectionAccountState>(&election_pda_account.data.borrow()).unwrap();
+    let account_data: ElectionAccountState = load_account::<ElectionAccountState>(election_pda_account)?;
     
     let number_of_votes = account_data.number_of_votes;
 
-    return Ok(number_of_votes)
+    Ok(number_of_votes)
 }
\ No newline at end of file
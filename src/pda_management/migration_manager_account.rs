@@ -0,0 +1,113 @@
+use solana_program::{
+    entrypoint::ProgramResult,
+    pubkey::Pubkey,
+    msg,
+    account_info::{next_account_info, AccountInfo},
+    program_error::ProgramError
+};
+
+use crate::{
+    state::{account_header::AccountState, election_account_state::ElectionAccountState, candidate_list_state::CandidateListState, result_state::ResultState},
+    pda_management::result_manager_account::sort_and_add_results,
+    utilities::{account_size_utilities::save_with_realloc, account_state_utilities::{account_version, load_any_version}}
+};
+
+//Migrazione in place degli account di un'elezione al layout corrente: ogni account di versione
+//precedente viene letto, convertito con AccountState::upgrade e riscritto con la nuova intestazione,
+//ingrandendolo con realloc a spese dell'autorita'. Gli account gia' aggiornati restano invariati.
+//Un'elezione di versione 0 non ha un'autorita': la conversione e' deterministica e basta la firma di chi paga,
+//ma l'elezione convertita resta senza autorita' e le istruzioni riservate all'autorita' non sono disponibili.
+
+//AGGIORNA ELEZIONE, LISTA CANDIDATI E RISULTATI ALLA VERSIONE CORRENTE
+pub fn migrate_accounts(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    election_name: String
+) -> ProgramResult {
+
+    let account_info_iter = &mut accounts.iter();
+
+    let initializer = next_account_info(account_info_iter)?;
+    let payer = next_account_info(account_info_iter)?;
+    let system_program = next_account_info(account_info_iter)?;
+    let election_pda_account = next_account_info(account_info_iter)?;
+    let candidate_list_pda_account = next_account_info(account_info_iter)?;
+    let result_pda_account = next_account_info(account_info_iter)?;
+
+    //IL TIPO DI OGNI ACCOUNT E' GARANTITO DAI SEED, ANCHE SENZA DISCRIMINATORE
+    let (election_pda, _election_bump_seed) = Pubkey::find_program_address(
+        &[program_id.as_ref(), election_name.as_bytes()],
+        program_id
+    );
+    let (candidate_list_pda, _candidate_list_bump_seed) = Pubkey::find_program_address(
+        &[program_id.as_ref(), election_name.as_bytes(), "candidate-list".as_bytes()],
+        program_id
+    );
+    let (result_pda, _result_bump_seed) = Pubkey::find_program_address(
+        &[program_id.as_ref(), election_name.as_bytes(), "result".as_bytes()],
+        program_id
+    );
+    if election_pda != *election_pda_account.key || candidate_list_pda != *candidate_list_pda_account.key || result_pda != *result_pda_account.key {
+        msg!("Invalid seeds for PDA");
+        return Err(ProgramError::InvalidSeeds)
+    }
+    if election_pda_account.owner != program_id || candidate_list_pda_account.owner != program_id || result_pda_account.owner != program_id {
+        return Err(ProgramError::IllegalOwner)
+    }
+
+    let is_legacy_election = account_version::<ElectionAccountState>(election_pda_account) == 0;
+    let election_data = load_any_version::<ElectionAccountState>(election_pda_account)?;
+
+    if !initializer.is_signer || (!is_legacy_election && election_data.authority != *initializer.key) {
+        msg!("Only the election authority can migrate its accounts");
+        return Err(ProgramError::MissingRequiredSignature)
+    }
+
+    let candidate_list_data = load_any_version::<CandidateListState>(candidate_list_pda_account)?;
+    let mut result_data = load_any_version::<ResultState>(result_pda_account)?;
+
+    //ELEZIONE E LISTA CONVERTITE ORDINANO ENTRAMBE I CANDIDATI PER INDIRIZZO: GLI INDICI DEVONO COINCIDERE
+    if account_version::<CandidateListState>(candidate_list_pda_account) == 0
+        && !candidate_list_data.candidates.iter().map(|candidate| candidate.address).eq(election_data.candidates.iter().copied()) {
+        msg!("Candidate list out of sync with the election");
+        return Err(ProgramError::InvalidAccountData)
+    }
+    //IL RISULTATO CONVERTITO RECUPERA INDICE E INDIRIZZO DAL NOME E RIFA' LA CLASSIFICA
+    if account_version::<ResultState>(result_pda_account) == 0 {
+        let mut vote_counts = std::mem::take(&mut result_data.ranking);
+        for entry in vote_counts.iter_mut() {
+            let index = candidate_list_data.index_of(&entry.name).ok_or_else(|| {
+                msg!("Candidate {} is not in the candidate list", entry.name);
+                ProgramError::InvalidAccountData
+            })?;
+            entry.index = index as u32;
+            entry.candidate = candidate_list_data.candidates[index].address;
+        }
+        sort_and_add_results(&mut result_data, vote_counts);
+    }
+
+    migrate_account(election_pda_account, election_data, payer, system_program)?;
+    migrate_account(candidate_list_pda_account, candidate_list_data, payer, system_program)?;
+    migrate_account(result_pda_account, result_data, payer, system_program)?;
+
+    Ok(())
+}
+
+//RISCRIVE LO STATO NEL LAYOUT CORRENTE SE L'ACCOUNT E' DI UNA VERSIONE PRECEDENTE
+fn migrate_account<'a, T: AccountState>(
+    account: &AccountInfo<'a>,
+    state: T,
+    payer: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>
+) -> ProgramResult {
+    let version = account_version::<T>(account);
+    if version == T::VERSION {
+        msg!("Account {} already at version {}", account.key, version);
+        return Ok(())
+    }
+
+    save_with_realloc(&state, account, payer, system_program)?;
+    msg!("Account {} migrated from version {} to {}", account.key, version, T::VERSION);
+
+    Ok(())
+}
@@ -2,4 +2,12 @@ pub mod candidate_manager_account;
 pub mod election_manager_account;
 pub mod candidate_list_manager_account;
 pub mod voter_manager_accout;
-pub mod result_manager_account;
\ No newline at end of file
+pub mod result_manager_account;
+pub mod district_manager_account;
+pub mod roll_manager_account;
+pub mod delegation_manager_account;
+pub mod randomness_manager_account;
+pub mod shard_manager_account;
+pub mod vote_counters_manager_account;
+pub mod migration_manager_account;
+pub mod archive_manager_account;
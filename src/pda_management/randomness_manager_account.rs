@@ -0,0 +1,334 @@
+use std::collections::{BTreeMap, HashSet};
+
+use solana_program::{
+    entrypoint::ProgramResult,
+    pubkey::Pubkey,
+    msg,
+    account_info::{next_account_info, AccountInfo},
+    program_error::ProgramError,
+    hash::hashv
+};
+
+use crate::{
+    state::election_account_state::{ElectionAccountState, RandomnessRound},
+    pda_management::election_manager_account::voting_started,
+    utilities::{election_account_utilities::{is_election_closed, is_election_started, is_retention_elapsed}, account_size_utilities::save_with_realloc, account_state_utilities::load_account}
+};
+
+//NUMERO MINIMO E MASSIMO DI GARANTI PER ELEZIONE
+pub const MIN_TRUSTEES: usize = 2;
+const MAX_TRUSTEES: usize = 16;
+//TEMPO CONCESSO AI GARANTI PER RIVELARE IL SEGRETO DELLO SPAREGGIO DOPO LA CHIUSURA (3 GIORNI)
+pub const REVEAL_WINDOW_SECONDS: i64 = 3 * 24 * 60 * 60;
+
+//Semi casuali con schema commit-reveal tra i garanti dell'elezione, che non possono includere l'autorita':
+//ogni garante impegna hash(segreto, chiave del garante) e rivela il segreto quando gli impegni sono chiusi.
+//Il seme e' hash dei segreti rivelati nell'ordine dei garanti, quindi basta un garante onesto perche' sia imprevedibile.
+//Due estrazioni indipendenti:
+// - ordine di scheda: impegni prima dell'inizio del voto, rivelazioni quando tutti i garanti hanno impegnato;
+// - spareggio per sorteggio: impegni prima della chiusura, rivelazioni dopo la chiusura.
+//Se un garante non rivela, scaduto il termine chiunque puo' chiudere l'estrazione con i segreti rivelati
+//(almeno uno). Limite di distorsione: un garante che conosce gli altri segreti sceglie solo se rivelare il proprio,
+//quindi con k garanti che trattengono il segreto il seme e' uno tra al piu' 2^k valori possibili.
+
+//IMPEGNI, RIVELAZIONI E SEME DI UN'ESTRAZIONE
+struct Draw<'a> {
+    commitments: &'a mut BTreeMap<Pubkey, [u8; 32]>,
+    reveals: &'a mut BTreeMap<Pubkey, [u8; 32]>,
+}
+
+fn draw_mut(
+    account_data: &mut ElectionAccountState,
+    round: RandomnessRound
+) -> Draw<'_> {
+    match round {
+        RandomnessRound::TieBreak => Draw {
+            commitments: &mut account_data.randomness_commitments,
+            reveals: &mut account_data.randomness_reveals,
+        },
+        RandomnessRound::BallotOrder => Draw {
+            commitments: &mut account_data.ballot_commitments,
+            reveals: &mut account_data.ballot_reveals,
+        },
+    }
+}
+
+fn is_draw_complete(
+    account_data: &ElectionAccountState,
+    round: RandomnessRound
+) -> bool {
+    match round {
+        RandomnessRound::TieBreak => account_data.random_seed_ready,
+        RandomnessRound::BallotOrder => !account_data.ballot_order.is_empty(),
+    }
+}
+
+//ORDINE DI SCHEDA: INDICI DEI CANDIDATI ORDINATI PER hash(seme, indice)
+pub fn ballot_order(
+    seed: &[u8; 32],
+    candidates: usize
+) -> Vec<u32> {
+    let mut order: Vec<u32> = (0..candidates as u32).collect();
+    order.sort_by_cached_key(|index| hashv(&[seed, &index.to_le_bytes()]).to_bytes());
+    order
+}
+
+//CALCOLA IL SEME DAI SEGRETI RIVELATI NELL'ORDINE DEI GARANTI E CHIUDE L'ESTRAZIONE
+fn complete_draw(
+    account_data: &mut ElectionAccountState,
+    round: RandomnessRound
+) {
+    let reveals = match round {
+        RandomnessRound::TieBreak => &account_data.randomness_reveals,
+        RandomnessRound::BallotOrder => &account_data.ballot_reveals,
+    };
+    let secrets: Vec<&[u8]> = account_data.trustees.iter().filter_map(|key| reveals.get(key)).map(|secret| secret.as_ref()).collect();
+    let seed = hashv(&secrets);
+    msg!("Random seed for {} ({:?}): {}", account_data.name, round, seed);
+
+    match round {
+        RandomnessRound::TieBreak => {
+            account_data.random_seed = seed.to_bytes();
+            account_data.random_seed_ready = true;
+        }
+        RandomnessRound::BallotOrder => {
+            account_data.ballot_seed = seed.to_bytes();
+            account_data.ballot_order = ballot_order(&account_data.ballot_seed, account_data.candidates.len());
+        }
+    }
+}
+
+//VERIFICA IL PDA DELL'ELEZIONE E NE LEGGE LO STATO
+fn load_election(
+    program_id: &Pubkey,
+    election_pda_account: &AccountInfo,
+    election_name: &str
+) -> Result<ElectionAccountState, ProgramError> {
+    let (election_pda, _election_bump_seed) = Pubkey::find_program_address(
+        &[program_id.as_ref(), election_name.as_bytes()],
+        program_id
+    );
+    if election_pda != *election_pda_account.key {
+        msg!("Invalid seeds for PDA");
+        return Err(ProgramError::InvalidSeeds)
+    }
+    if election_pda_account.owner != program_id {
+        return Err(ProgramError::IllegalOwner)
+    }
+
+    load_account::<ElectionAccountState>(election_pda_account)
+}
+
+//NOMINA I GARANTI DEI SEMI CASUALI, PRIMA DEL VOTO
+pub fn set_trustees(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    election_name: String,
+    trustees: Vec<Pubkey>
+) -> ProgramResult {
+
+    let account_info_iter = &mut accounts.iter();
+
+    let initializer = next_account_info(account_info_iter)?;
+    let payer = next_account_info(account_info_iter)?;
+    let election_pda_account = next_account_info(account_info_iter)?;
+    let system_program = next_account_info(account_info_iter)?;
+
+    let mut account_data = load_election(program_id, election_pda_account, &election_name)?;
+
+    if !initializer.is_signer || account_data.authority != *initializer.key {
+        msg!("Only the election authority can appoint trustees");
+        return Err(ProgramError::MissingRequiredSignature)
+    }
+    if voting_started(&account_data)? || !account_data.randomness_commitments.is_empty() || !account_data.ballot_commitments.is_empty() {
+        msg!("Trustees cannot change after voting or committing has started");
+        return Err(ProgramError::InvalidAccountData)
+    }
+    let distinct_trustees: HashSet<&Pubkey> = trustees.iter().collect();
+    if trustees.len() < MIN_TRUSTEES || trustees.len() > MAX_TRUSTEES || distinct_trustees.len() != trustees.len() {
+        msg!("Expected between {} and {} distinct trustees", MIN_TRUSTEES, MAX_TRUSTEES);
+        return Err(ProgramError::InvalidArgument)
+    }
+    //L'AUTORITA' CONOSCE I VOTI: NON PUO' CONTRIBUIRE AL SEME
+    if distinct_trustees.contains(&account_data.authority) {
+        msg!("The election authority cannot be a trustee");
+        return Err(ProgramError::InvalidArgument)
+    }
+
+    msg!("{} trustees for {}", trustees.len(), election_name);
+    account_data.trustees = trustees;
+    save_with_realloc(&account_data, election_pda_account, payer, system_program)?;
+
+    Ok(())
+}
+
+//REGISTRA L'IMPEGNO DI UN GARANTE PER UN'ESTRAZIONE
+pub fn commit_randomness(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    election_name: String,
+    round: RandomnessRound,
+    commitment: [u8; 32]
+) -> ProgramResult {
+
+    let account_info_iter = &mut accounts.iter();
+
+    let trustee = next_account_info(account_info_iter)?;
+    let payer = next_account_info(account_info_iter)?;
+    let election_pda_account = next_account_info(account_info_iter)?;
+    let system_program = next_account_info(account_info_iter)?;
+
+    let mut account_data = load_election(program_id, election_pda_account, &election_name)?;
+
+    if !trustee.is_signer || !account_data.trustees.contains(trustee.key) {
+        msg!("{} is not a trustee of {}", trustee.key, election_name);
+        return Err(ProgramError::MissingRequiredSignature)
+    }
+    match round {
+        RandomnessRound::TieBreak => if is_election_closed(&account_data.end_date)? {
+            msg!("Commitments are closed for {}", election_name);
+            return Err(ProgramError::InvalidAccountData)
+        },
+        //L'ORDINE DI SCHEDA SI ESTRAE SULLA LISTA CANDIDATI DEFINITIVA, PRIMA DEL VOTO
+        RandomnessRound::BallotOrder => {
+            if voting_started(&account_data)? {
+                msg!("Ballot order commitments are closed for {}", election_name);
+                return Err(ProgramError::InvalidAccountData)
+            }
+            if account_data.candidates.len() < 2 {
+                msg!("Election {} needs at least two candidates to draw the ballot order", election_name);
+                return Err(ProgramError::InvalidArgument)
+            }
+        }
+    }
+    if commitment == [0; 32] {
+        return Err(ProgramError::InvalidArgument)
+    }
+    //L'IMPEGNO NON PUO' ESSERE SOSTITUITO
+    let draw = draw_mut(&mut account_data, round);
+    if draw.commitments.contains_key(trustee.key) {
+        msg!("Trustee {} has already committed", trustee.key);
+        return Err(ProgramError::AccountAlreadyInitialized)
+    }
+    draw.commitments.insert(*trustee.key, commitment);
+
+    save_with_realloc(&account_data, election_pda_account, payer, system_program)?;
+
+    Ok(())
+}
+
+//RIVELA IL SEGRETO DI UN GARANTE; CON L'ULTIMA RIVELAZIONE CALCOLA IL SEME
+pub fn reveal_randomness(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    election_name: String,
+    round: RandomnessRound,
+    secret: [u8; 32]
+) -> ProgramResult {
+
+    let account_info_iter = &mut accounts.iter();
+
+    let trustee = next_account_info(account_info_iter)?;
+    let payer = next_account_info(account_info_iter)?;
+    let election_pda_account = next_account_info(account_info_iter)?;
+    let system_program = next_account_info(account_info_iter)?;
+
+    let mut account_data = load_election(program_id, election_pda_account, &election_name)?;
+
+    if !trustee.is_signer {
+        return Err(ProgramError::MissingRequiredSignature)
+    }
+    if is_draw_complete(&account_data, round) {
+        msg!("The {:?} draw of {} is already complete", round, election_name);
+        return Err(ProgramError::AccountAlreadyInitialized)
+    }
+    //LE RIVELAZIONI INIZIANO SOLO QUANDO NESSUN IMPEGNO PUO' PIU' ESSERE AGGIUNTO
+    let commitments_closed = match round {
+        RandomnessRound::TieBreak => is_election_closed(&account_data.end_date)?,
+        RandomnessRound::BallotOrder => account_data.trustees.iter().all(|key| account_data.ballot_commitments.contains_key(key)),
+    };
+    if !commitments_closed {
+        msg!("Secrets can be revealed only after the commitments have closed");
+        return Err(ProgramError::InvalidAccountData)
+    }
+
+    let trustees = account_data.trustees.clone();
+    let draw = draw_mut(&mut account_data, round);
+    let commitment = *draw.commitments.get(trustee.key).ok_or(ProgramError::InvalidAccountData)?;
+    if draw.reveals.contains_key(trustee.key) {
+        msg!("Trustee {} has already revealed", trustee.key);
+        return Err(ProgramError::AccountAlreadyInitialized)
+    }
+    if hashv(&[&secret, trustee.key.as_ref()]).to_bytes() != commitment {
+        msg!("Secret does not match the commitment of {}", trustee.key);
+        return Err(ProgramError::InvalidArgument)
+    }
+    draw.reveals.insert(*trustee.key, secret);
+
+    if trustees.iter().all(|key| draw.reveals.contains_key(key)) {
+        complete_draw(&mut account_data, round);
+    }
+
+    save_with_realloc(&account_data, election_pda_account, payer, system_program)?;
+
+    Ok(())
+}
+
+//CHIUDE UN'ESTRAZIONE CON I SEGRETI RIVELATI QUANDO UN GARANTE NON HA RIVELATO ENTRO IL TERMINE:
+//L'INIZIO DEL VOTO PER L'ORDINE DI SCHEDA, REVEAL_WINDOW_SECONDS DOPO LA CHIUSURA PER LO SPAREGGIO
+pub fn finalize_randomness(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    election_name: String,
+    round: RandomnessRound
+) -> ProgramResult {
+
+    let account_info_iter = &mut accounts.iter();
+
+    let initializer = next_account_info(account_info_iter)?;
+    let payer = next_account_info(account_info_iter)?;
+    let election_pda_account = next_account_info(account_info_iter)?;
+    let system_program = next_account_info(account_info_iter)?;
+
+    let mut account_data = load_election(program_id, election_pda_account, &election_name)?;
+
+    if !initializer.is_signer {
+        return Err(ProgramError::MissingRequiredSignature)
+    }
+    if is_draw_complete(&account_data, round) {
+        msg!("The {:?} draw of {} is already complete", round, election_name);
+        return Err(ProgramError::AccountAlreadyInitialized)
+    }
+    let deadline_passed = match round {
+        RandomnessRound::TieBreak => is_retention_elapsed(&account_data.end_date, REVEAL_WINDOW_SECONDS)?,
+        RandomnessRound::BallotOrder => is_election_started(&account_data.start_date)?,
+    };
+    if !deadline_passed {
+        msg!("Trustees can still reveal for {}", election_name);
+        return Err(ProgramError::InvalidAccountData)
+    }
+    if draw_mut(&mut account_data, round).reveals.is_empty() {
+        msg!("No trustee has revealed for {}", election_name);
+        return Err(ProgramError::InvalidAccountData)
+    }
+
+    complete_draw(&mut account_data, round);
+    save_with_realloc(&account_data, election_pda_account, payer, system_program)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ballot_order_is_a_deterministic_permutation() {
+        let order = ballot_order(&[7; 32], 10);
+        let mut sorted = order.clone();
+        sorted.sort();
+        assert_eq!(sorted, (0..10).collect::<Vec<u32>>());
+        assert_eq!(order, ballot_order(&[7; 32], 10));
+        assert_ne!(order, ballot_order(&[8; 32], 10));
+    }
+}
@@ -1,20 +1,24 @@
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 
 use solana_program::{
     entrypoint::ProgramResult,
     pubkey::Pubkey,
     msg,
+    program_error::ProgramError,
     account_info::{next_account_info, AccountInfo},
     system_instruction,
     sysvar::{rent::Rent, Sysvar},
     program::invoke_signed,
-    borsh0_10::try_from_slice_unchecked
+    hash::hashv
 };
 
-use borsh::BorshSerialize;
-use crate::{candidate_list_manager_account::retrieve_candidate_list, state::result_state::ResultState};
+use crate::{candidate_list_manager_account::retrieve_candidate_list, state::{result_state::{ResultState, RankedEntry, TieBreakRecord, ElectionOutcome}, election_account_state::{ElectionAccountState, TieBreakPolicy, Quorum, MajorityRule}, candidate_list_state::CandidateListState, vote_counters_state::VoteCountersHeader}};
 
-use super::election_manager_account::{get_percentage_of_votes, get_number_of_votes};
+use super::election_manager_account::{check_tally_allowed, finalize_tally};
+use super::delegation_manager_account::resolve_delegated_votes;
+use super::shard_manager_account::validate_shard_account;
+use super::vote_counters_manager_account::{validate_vote_counters_account, read_counters_totals, read_candidate_counter};
+use crate::utilities::{account_size_utilities::save_with_realloc, account_state_utilities::{load_account, save_account, pack_account}};
 
 pub fn generate_result_account (
     program_id: &Pubkey,
@@ -26,115 +30,564 @@ pub fn generate_result_account (
 
     let account_info_iter = &mut accounts.iter();
 
-    let initializer = next_account_info(account_info_iter)?;
+    let _initializer = next_account_info(account_info_iter)?;
+    let payer = next_account_info(account_info_iter)?;
     let system_program = next_account_info(account_info_iter)?;
     let _election_pda_account = next_account_info(account_info_iter)?;
     let _candidate_list_pda_account = next_account_info(account_info_iter)?;
     let result_pda_account = next_account_info(account_info_iter)?;
 
     let (result_pda, result_bump_seed) = Pubkey::find_program_address(
-        &[program_id.as_ref(), election_name.as_bytes().as_ref(), seed.as_bytes().as_ref()],
+        &[program_id.as_ref(), election_name.as_bytes(), seed.as_bytes()],
          program_id
         );    
-    let account_len: usize = 10000;
+    //DIMENSIONE DEL RISULTATO VUOTO: LO SCRUTINIO INGRANDISCE L'ACCOUNT CON REALLOC
+    let account_len: usize = pack_account(&ResultState::default())?.len();
 
     let rent = Rent::get()?;
     let rent_lamports = rent.minimum_balance(account_len);
 
     invoke_signed(
         &system_instruction::create_account(
-            initializer.key, 
+            payer.key, 
             result_pda_account.key, 
             rent_lamports,
             account_len.try_into().unwrap(), 
             program_id
         ), 
-        &[initializer.clone(),result_pda_account.clone(),system_program.clone()], 
-        &[&[program_id.as_ref(),election_name.as_bytes().as_ref(),seed.as_bytes().as_ref(), &[result_bump_seed]]]
+        &[payer.clone(), result_pda_account.clone(),system_program.clone()], 
+        &[&[program_id.as_ref(),election_name.as_bytes(),seed.as_bytes(), &[result_bump_seed]]]
     )?;
 
     msg!("PDA Created: {}",result_pda);
 
+    //SCRIVE IL RISULTATO VUOTO CON LA SUA INTESTAZIONE
+    save_account(&ResultState::default(), result_pda_account)?;
+
     Ok(())
 }
 
 
+//SCRUTINIO DEFINITIVO DELL'ELEZIONE: UNA SOLA VOLTA, SALVO RICONTEGGIO
 pub fn counting_votes(
-    accounts: &[AccountInfo]
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    election_name: String
+) -> ProgramResult {
+    counting_votes_page(program_id, accounts, election_name, u32::MAX)
+}
+
+//SCRUTINIO A PAGINE PER ELEZIONI CON MOLTI CANDIDATI
+//Ogni chiamata conta al piu' page_size candidati, in ordine di indice, e salva il cursore nel risultato.
+//L'ultima pagina applica deleghe (negli account restanti), spareggi e quorum e chiude lo scrutinio.
+//Ogni pagina riceve l'account contatori dopo il system program, seguito dagli eventuali contatori distribuiti,
+//e ne legge solo i contatori dei candidati della pagina: affluenza e peso totale sono letti alla prima
+//pagina e restano nel risultato insieme ai conteggi parziali.
+pub fn counting_votes_page(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    election_name: String,
+    page_size: u32
 ) -> ProgramResult {
     
     let account_info_iter = &mut accounts.iter();
 
-    let _initializer = next_account_info(account_info_iter)?;
+    let initializer = next_account_info(account_info_iter)?;
+    let payer = next_account_info(account_info_iter)?;
     let election_pda_account = next_account_info(account_info_iter)?;
     let candidate_list_pda_account = next_account_info(account_info_iter)?;
     let result_pda_account = next_account_info(account_info_iter)?;
-    let _system_program = next_account_info(account_info_iter)?;
+    let system_program = next_account_info(account_info_iter)?;
+    let vote_counters_pda_account = next_account_info(account_info_iter)?;
+
+    let (election_pda, _election_bump_seed) = Pubkey::find_program_address(
+        &[program_id.as_ref(), election_name.as_bytes()],
+        program_id
+    );
+    let (result_pda, _result_bump_seed) = Pubkey::find_program_address(
+        &[program_id.as_ref(), election_name.as_bytes(), "result".as_bytes()],
+        program_id
+    );
+    if election_pda != *election_pda_account.key || election_pda_account.owner != program_id {
+        msg!("Invalid election account");
+        return Err(ProgramError::InvalidSeeds)
+    }
+    if result_pda != *result_pda_account.key || result_pda_account.owner != program_id {
+        msg!("Invalid result account");
+        return Err(ProgramError::InvalidSeeds)
+    }
+    if page_size == 0 {
+        return Err(ProgramError::InvalidArgument)
+    }
+
+    //L'ELEZIONE VIENE LETTA UNA SOLA VOLTA PER TUTTA LA PAGINA
+    let election_data = load_account::<ElectionAccountState>(election_pda_account)?;
+    check_tally_allowed(initializer, &election_data)?;
+
+    //L'ACCOUNT CONTATORI E I CONTATORI DISTRIBUITI, PASSATI PRIMA DELLE DELEGHE
+    validate_vote_counters_account(program_id, vote_counters_pda_account, &election_data.name)?;
+    if vote_counters_pda_account.data_len() != VoteCountersHeader::space(election_data.candidates.len()) {
+        msg!("Vote counters out of sync with the candidates");
+        return Err(ProgramError::InvalidAccountData)
+    }
+    let mut counters_pda_accounts = vec![vote_counters_pda_account];
+    for index in 0..election_data.number_of_shards {
+        let shard_pda_account = next_account_info(account_info_iter)?;
+        validate_shard_account(program_id, shard_pda_account, &election_data.name, index)?;
+        counters_pda_accounts.push(shard_pda_account);
+    }
+
+    let mut result_data = load_account::<ResultState>(result_pda_account)?;
+
+    //UNO SCRUTINIO NUOVO RIPARTE DA UN RISULTATO VUOTO, SENZA UNIRE CONTEGGI PRECEDENTI:
+    //AFFLUENZA E PESO TOTALE SI LEGGONO UNA VOLTA E RESTANO NEL RISULTATO FINO ALL'ULTIMA PAGINA
+    if !result_data.tally_in_progress {
+        result_data = ResultState::default();
+        result_data.tally_in_progress = true;
+        let (mut number_of_votes, mut total_weight) = (election_data.number_of_votes, election_data.total_weight);
+        for counters_pda_account in &counters_pda_accounts {
+            let (counted_votes, counted_weight) = read_counters_totals(counters_pda_account)?;
+            number_of_votes += counted_votes;
+            total_weight += counted_weight;
+        }
+        add_number_of_votes(&mut result_data, number_of_votes, total_weight);
+    }
 
-    //RECUPERA L'HASHMAP DEI CANDIDATI
+    //I CANDIDATI SONO GIA' IN ORDINE DI INDICE, STABILE TRA UNA PAGINA E L'ALTRA
     let candidate_list = retrieve_candidate_list(candidate_list_pda_account)?;
-    
+    if candidate_list.len() != election_data.candidates.len() {
+        msg!("Candidate list out of sync with the election");
+        return Err(ProgramError::InvalidAccountData)
+    }
+
+    let start = (result_data.tally_cursor as usize).min(candidate_list.len());
+    let end = start.saturating_add(page_size as usize).min(candidate_list.len());
+
+    //I CONTEGGI PARZIALI RESTANO NELLA CLASSIFICA CON POSIZIONE 0 FINO ALL'ULTIMA PAGINA
+    for (index, candidate) in candidate_list.iter().enumerate().take(end).skip(start) {
+        let (mut votes, mut voters) = (election_data.votes[index], election_data.headcount[index]);
+        for counters_pda_account in &counters_pda_accounts {
+            let counter = read_candidate_counter(counters_pda_account, index)?;
+            votes += counter.votes;
+            voters += counter.headcount;
+        }
+        result_data.ranking.push(RankedEntry {
+            rank: 0,
+            index: index as u32,
+            candidate: candidate.address,
+            name: candidate.name.clone(),
+            votes,
+            share: 0,
+            tied: false,
+        });
+        result_data.headcount.insert(candidate.name.clone(), voters);
+    }
+    result_data.tally_cursor = end as u32;
+
+    if end < candidate_list.len() {
+        msg!("Scrutinati {} candidati su {}", end, candidate_list.len());
+        return save_with_realloc(&result_data, result_pda_account, payer, system_program)
+    }
+
+    //RISOLVE LE DELEGHE FORNITE NEGLI ACCOUNT RESTANTI
+    let delegated_votes = resolve_delegated_votes(program_id, &election_data, account_info_iter)?;
+    let delegated_weight: i64 = delegated_votes.values().map(|(weight, _)| weight).sum();
+    let delegated_voters: i64 = delegated_votes.values().map(|(_, voters)| voters).sum();
+    if delegated_voters > 0 {
+        msg!("Voti per delega: {}", delegated_voters);
+    }
+
+    let mut vote_counts: Vec<RankedEntry> = Vec::with_capacity(result_data.ranking.len());
+    let mut headcount: BTreeMap<String,i64> = BTreeMap::new();
+    for mut entry in result_data.ranking.drain(..) {
+        let (candidate_delegated_weight, candidate_delegated_voters) = delegated_votes.get(&entry.candidate).copied().unwrap_or((0, 0));
+        let voters = *result_data.headcount.get(&entry.name).unwrap_or(&0);
+        headcount.insert(entry.name.clone(), voters + candidate_delegated_voters);
+        entry.votes += candidate_delegated_weight;
+        vote_counts.push(entry);
+    }
+    result_data.tally_in_progress = false;
+
     //OTTIENE NUMERO TOTALE VOTI E LO INSERISCE IN RESULT
-    let total_number_of_votes = get_number_of_votes(election_pda_account)?;
-    let _ = add_number_of_votes(result_pda_account, total_number_of_votes);
+    let (counted_votes, counted_weight) = (result_data.number_of_votes, result_data.total_weight);
+    add_number_of_votes(&mut result_data, counted_votes + delegated_voters, counted_weight + delegated_weight);
     //AGGIUNGE E STAMPA I RISULTATI
-    let _ = add_and_show_result(candidate_list, election_pda_account, result_pda_account);
-    
-    Ok(())
+    add_headcount_results(&mut result_data, &headcount);
+    sort_and_add_results(&mut result_data, vote_counts);
+    //APPLICA LA REGOLA DI SPAREGGIO DELL'ELEZIONE
+    break_ties(&mut result_data, &election_data, candidate_list_pda_account)?;
+    //VERIFICA QUORUM E MAGGIORANZA
+    evaluate_outcome(&mut result_data, &election_data)?;
+
+    //RENDE IL RISULTATO DEFINITIVO E LO SALVA, INGRANDENDO L'ACCOUNT SE NECESSARIO
+    finalize_tally(initializer, election_pda_account, &mut result_data)?;
+    save_with_realloc(&result_data, result_pda_account, payer, system_program)
 
 }
 
-pub fn add_and_show_result (
-    candidate_list: HashMap<String,Pubkey>,
-    election_pda_account: &AccountInfo,
-    result_pda_account: &AccountInfo
-) -> ProgramResult {
-    let mut not_sorted_hash_map: HashMap<String,f32> = HashMap::new();
+pub fn add_number_of_votes (
+    account_data: &mut ResultState,
+    number_of_votes: i64,
+    total_weight: i64
+) {
+    account_data.number_of_votes = number_of_votes;
+    account_data.total_weight = total_weight;
+}
 
-    for (candidate_info, candidate_pda_address) in candidate_list {
-        let percentage_for_candidate = get_percentage_of_votes(election_pda_account, candidate_pda_address)?;
-        not_sorted_hash_map.insert(candidate_info, percentage_for_candidate);  
+//QUOTA IN PUNTI BASE (10000 = 100%) CALCOLATA SOLO CON ARITMETICA INTERA
+//Regola di arrotondamento: per difetto, floor(votes * 10000 / total), quindi le quote possono
+//sommare a meno di 10000. Senza voti (total = 0) la quota di ogni candidato e' 0.
+pub fn basis_points(
+    votes: i64,
+    total: i64
+) -> u32 {
+    if total <= 0 || votes <= 0 {
+        return 0
     }
-    return sort_and_add_results(result_pda_account, not_sorted_hash_map);
+    (i128::from(votes) * 10_000 / i128::from(total)) as u32
 }
 
-pub fn add_number_of_votes (
-    result_pda_account: &AccountInfo,
-    number_of_votes: i64
+//MAGGIORANZA ASSOLUTA: PIU' DELLA META' DEL TOTALE, SENZA ARROTONDAMENTI
+pub fn has_majority(
+    votes: i64,
+    total: i64
+) -> bool {
+    total > 0 && votes > total - votes
+}
+
+//REGISTRA I VOTANTI DI OGNI CANDIDATO E LA MAGGIORANZA PER TESTE
+pub fn add_headcount_results (
+    account_data: &mut ResultState,
+    headcount: &BTreeMap<String,i64>
+) {
+    account_data.headcount = headcount.clone();
+    account_data.headcount_majority = String::new();
+
+    for (candidate_info, voters) in headcount {
+        if has_majority(*voters, account_data.number_of_votes) {
+            account_data.headcount_majority = candidate_info.clone();
+        }
+        account_data.headcount_results.insert(candidate_info.clone(), basis_points(*voters, account_data.number_of_votes));
+    }
+}
+
+//I CONTEGGI ARRIVANO COME VOCI DELLA CLASSIFICA CON INDICE, CANDIDATO, NOME E VOTI
+pub fn sort_and_add_results (
+    account_data: &mut ResultState,
+    mut vote_counts: Vec<RankedEntry>,
+) {
+    // ORDINA IL VETTORE IN ORDINE DECRESCENTE, A PARITA' PER NOME E PER INDICE
+    vote_counts.sort_by(|a, b| b.votes.cmp(&a.votes).then_with(|| a.name.cmp(&b.name)).then_with(|| a.index.cmp(&b.index)));
+
+    // COSTRUISCE LA CLASSIFICA: A PARI VOTI STESSA POSIZIONE
+    account_data.ranking = Vec::with_capacity(vote_counts.len());
+    for (position, entry) in vote_counts.iter().enumerate() {
+        let rank = match account_data.ranking.last() {
+            Some(previous) if previous.votes == entry.votes => previous.rank,
+            _ => position as u32 + 1,
+        };
+        let tied = vote_counts.iter().filter(|other| other.votes == entry.votes).count() > 1;
+        let share = basis_points(entry.votes, account_data.total_weight);
+        msg!("{}. {} ha ricevuto {} voti ({}.{:02}%){}", rank, entry.name, entry.votes, share / 100, share % 100, if tied { " - parita'" } else { "" });
+        account_data.ranking.push(RankedEntry {
+            rank,
+            share,
+            tied,
+            ..entry.clone()
+        });
+    }
+
+    // MAGGIORANZA PONDERATA SE UN CANDIDATO SUPERA IL 50%
+    account_data.weighted_majority = match account_data.ranking.first() {
+        Some(first) if has_majority(first.votes, account_data.total_weight) => first.name.clone(),
+        _ => String::new(),
+    };
+
+    msg!("Voti totali: {}",account_data.number_of_votes);
+    if account_data.total_weight != account_data.number_of_votes {
+        msg!("Peso totale: {}",account_data.total_weight);
+    }
+}
+
+
+//ORDINA I CANDIDATI A PARI VOTI SECONDO LA REGOLA DELL'ELEZIONE E REGISTRA LO SPAREGGIO
+//Con la regola Runoff i candidati restano a pari posizione e accedono tutti al ballottaggio
+pub fn break_ties (
+    account_data: &mut ResultState,
+    election_data: &ElectionAccountState,
+    candidate_list_pda_account: &AccountInfo
 ) -> ProgramResult {
 
-    let mut account_data: ResultState = try_from_slice_unchecked::<ResultState>(&result_pda_account.data.borrow()).unwrap();
+    let candidate_list_data = load_account::<CandidateListState>(candidate_list_pda_account)?;
+
+    let rule = election_data.tie_break;
+    let mut tie_breaks: Vec<TieBreakRecord> = Vec::new();
+
+    let mut start = 0;
+    while start < account_data.ranking.len() {
+        let votes = account_data.ranking[start].votes;
+        let end = start + account_data.ranking[start..].iter().take_while(|entry| entry.votes == votes).count();
+        let group = &mut account_data.ranking[start..end];
+        start = end;
+        if group.len() < 2 {
+            continue
+        }
+
+        //ORDINAMENTO STABILE: A PARITA' ANCHE DELLO SPAREGGIO RESTA L'ORDINE PER NOME
+        let inputs: Vec<String> = match rule {
+            TieBreakPolicy::Runoff => Vec::new(),
+            TieBreakPolicy::OldestCandidate => {
+                //LE DATE YYYY-MM-DD SI CONFRONTANO COME STRINGHE, SENZA DATA IN CODA
+                let birth_date = |entry: &RankedEntry| candidate_list_data.candidates.get(entry.index as usize)
+                    .filter(|candidate| candidate.address == entry.candidate && !candidate.birth_date.is_empty())
+                    .map(|candidate| candidate.birth_date.clone());
+                group.sort_by_key(|entry| (birth_date(entry).is_none(), birth_date(entry)));
+                group.iter().map(|entry| birth_date(entry).unwrap_or_default()).collect()
+            }
+            TieBreakPolicy::EarlierRegistration => {
+                let registration_slot = |entry: &RankedEntry| candidate_list_data.candidates.get(entry.index as usize)
+                    .filter(|candidate| candidate.address == entry.candidate)
+                    .map_or(u64::MAX, |candidate| candidate.registration_slot);
+                group.sort_by_key(|entry| registration_slot(entry));
+                group.iter().map(|entry| registration_slot(entry).to_string()).collect()
+            }
+            TieBreakPolicy::Lot => {
+                if !election_data.random_seed_ready {
+                    msg!("Tie between candidates: the lot seed must be revealed or finalized before counting");
+                    return Err(ProgramError::InvalidAccountData)
+                }
+                let lot = |candidate: &Pubkey| hashv(&[&election_data.random_seed, candidate.as_ref()]);
+                group.sort_by_key(|entry| lot(&entry.candidate).to_bytes());
+                group.iter().map(|entry| lot(&entry.candidate).to_string()).collect()
+            }
+        };
+
+        let rank = group[0].rank;
+        if rule != TieBreakPolicy::Runoff {
+            for (offset, entry) in group.iter_mut().enumerate() {
+                entry.rank = rank + offset as u32;
+            }
+        }
+        msg!("Parita' per la posizione {} risolta con {:?}: {}", rank, rule, group.iter().map(|entry| entry.name.as_str()).collect::<Vec<_>>().join(", "));
+
+        tie_breaks.push(TieBreakRecord {
+            rule,
+            rank,
+            candidates: group.iter().map(|entry| entry.candidate).collect(),
+            inputs,
+        });
+    }
+
+    account_data.tie_breaks = tie_breaks;
+    account_data.lot_seed = if rule == TieBreakPolicy::Lot { election_data.random_seed } else { [0; 32] };
 
-    account_data.number_of_votes = number_of_votes;
-    account_data.serialize(&mut &mut result_pda_account.data.borrow_mut()[..])?;
-    
     Ok(())
 }
 
 
-pub fn sort_and_add_results (
-    result_pda_account: &AccountInfo,
-    not_sorted_hash_map: HashMap<String,f32>,
+//VOTANTI NECESSARI PER IL QUORUM
+//La quota sulle liste elettorali e' arrotondata per eccesso: 1/3 di 10 iscritti richiede 4 votanti.
+//Senza liste elettorali la quota non ha base di calcolo e lo scrutinio viene rifiutato.
+pub fn quorum_required(
+    election_data: &ElectionAccountState
+) -> Result<u64, ProgramError> {
+    match election_data.quorum {
+        Quorum::None => Ok(0),
+        Quorum::Absolute(voters) => Ok(voters),
+        Quorum::RollShare(share) => {
+            if election_data.roll_size <= 0 {
+                msg!("Election {} has a roll share quorum but no electoral roll", election_data.name);
+                return Err(ProgramError::InvalidAccountData)
+            }
+            let roll_size = election_data.roll_size as u128;
+            Ok((roll_size * share as u128).div_ceil(10_000) as u64)
+        }
+    }
+}
+
+//DETERMINA L'ESITO: QUORUM, VINCITORE SECONDO LA MAGGIORANZA RICHIESTA E NECESSITA' DI BALLOTTAGGIO
+pub fn evaluate_outcome (
+    account_data: &mut ResultState,
+    election_data: &ElectionAccountState
 ) -> ProgramResult {
+    account_data.quorum_required = quorum_required(election_data)?;
+    account_data.majority_rule = election_data.majority_rule;
+    account_data.winner = String::new();
+    account_data.runoff_required = false;
+
+    let turnout = account_data.number_of_votes.max(0) as u64;
+    let total = account_data.total_weight;
+
+    if turnout < account_data.quorum_required {
+        msg!("Quorum non raggiunto: {} votanti su {} necessari", turnout, account_data.quorum_required);
+        account_data.outcome = ElectionOutcome::QuorumNotReached;
+    } else {
+        let leader = account_data.ranking.first().filter(|leader| leader.votes > 0);
+        let elected = leader.filter(|leader| match election_data.majority_rule {
+            MajorityRule::Absolute => has_majority(leader.votes, total),
+            //PRIMO DA SOLO: ANCHE UNA PARITA' RISOLTA DALLO SPAREGGIO VALE
+            MajorityRule::Simple => account_data.ranking.iter().skip(1).all(|other| other.rank > leader.rank),
+            MajorityRule::TwoThirds => i128::from(leader.votes) * 3 >= i128::from(total) * 2,
+        });
+
+        match elected {
+            Some(leader) => {
+                msg!("Eletto {} con maggioranza {:?}", leader.name, election_data.majority_rule);
+                account_data.winner = leader.name.clone();
+                account_data.outcome = ElectionOutcome::Valid;
+            }
+            None => {
+                account_data.outcome = ElectionOutcome::NoMajority;
+                // BALLOTTAGGIO SE NESSUN CANDIDATO RAGGIUNGE LA MAGGIORANZA RICHIESTA
+                account_data.runoff_required = total > 0 && account_data.ranking.len() > 1;
+                if account_data.runoff_required {
+                    msg!("Nessun candidato ha la maggioranza {:?}: ballottaggio tra {} e {}", election_data.majority_rule, account_data.ranking[0].name, account_data.ranking[1].name);
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
 
-    let mut account_data: ResultState = try_from_slice_unchecked::<ResultState>(&result_pda_account.data.borrow()).unwrap();
 
-    let mut tuple_vec: Vec<_> = not_sorted_hash_map.into_iter().collect();
+//RESTITUISCE I DUE CANDIDATI AMMESSI AL BALLOTTAGGIO
+pub fn retrieve_runoff_finalists (
+    result_pda_account: &AccountInfo,
+) -> Result<Vec<String>, ProgramError> {
 
-    // ORDINA IL VETTORE IN ORDINE DECRESCENTE
-    tuple_vec.sort_by(|a, b| b.1.total_cmp(&a.1));
+    let account_data: ResultState = load_account::<ResultState>(result_pda_account)?;
 
-    for (k,v) in tuple_vec.clone()  {
-        msg!("Il candidato {} ha ricevuto il {}% dei voti", k,v);
-        account_data.results.insert(k,v);
+    if !account_data.is_final {
+        msg!("The first round has not been tallied");
+        return Err(ProgramError::InvalidAccountData)
+    }
+    if !account_data.runoff_required {
+        msg!("No runoff required for this election");
+        return Err(ProgramError::InvalidAccountData)
     }
 
-    msg!("Voti totali: {}",account_data.number_of_votes);
+    //LA CLASSIFICA E' GIA' ORDINATA E SPAREGGIATA: IL BALLOTTAGGIO E' TRA I PRIMI DUE
+    //Con la regola Runoff i pari merito restano alla stessa posizione: se piu' di due candidati
+    //occupano le prime due posizioni il ballottaggio non e' definito e viene rifiutato.
+    let finalists: Vec<String> = account_data.ranking.into_iter().filter(|entry| entry.rank <= 2).map(|entry| entry.name).collect();
+    if finalists.len() != 2 {
+        msg!("{} candidates tied for the runoff, set a tie-break policy and recount", finalists.len());
+        return Err(ProgramError::InvalidAccountData)
+    }
 
-  
-    account_data.serialize(&mut &mut result_pda_account.data.borrow_mut()[..])?;
+    Ok(finalists)
+}
 
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    Ok(())
+    #[test]
+    fn basis_points_round_down() {
+        assert_eq!(basis_points(1, 3), 3333);
+        assert_eq!(basis_points(2, 3), 6666);
+        assert_eq!(basis_points(3, 3), 10_000);
+    }
+
+    #[test]
+    fn basis_points_without_votes_are_zero() {
+        assert_eq!(basis_points(5, 0), 0);
+        assert_eq!(basis_points(5, -1), 0);
+        assert_eq!(basis_points(0, 10), 0);
+    }
+
+    fn election(quorum: Quorum, majority_rule: MajorityRule, roll_size: i64) -> ElectionAccountState {
+        ElectionAccountState {
+            name: "Elettorale1".to_string(),
+            quorum,
+            majority_rule,
+            roll_size,
+            ..Default::default()
+        }
+    }
+
+    fn result(votes: &[i64], number_of_votes: i64) -> ResultState {
+        let mut result_data = ResultState::default();
+        add_number_of_votes(&mut result_data, number_of_votes, votes.iter().sum());
+        let vote_counts = votes.iter().enumerate().map(|(index, votes)| RankedEntry {
+            rank: 0,
+            index: index as u32,
+            candidate: Pubkey::new_unique(),
+            name: format!("Candidato {}", index),
+            votes: *votes,
+            share: 0,
+            tied: false,
+        }).collect();
+        sort_and_add_results(&mut result_data, vote_counts);
+        result_data
+    }
+
+    #[test]
+    fn roll_share_quorum_rounds_up() {
+        assert_eq!(quorum_required(&election(Quorum::RollShare(3_334), MajorityRule::Simple, 10)).unwrap(), 4);
+        assert_eq!(quorum_required(&election(Quorum::RollShare(5_000), MajorityRule::Simple, 10)).unwrap(), 5);
+        assert_eq!(quorum_required(&election(Quorum::RollShare(1), MajorityRule::Simple, 3)).unwrap(), 1);
+        assert_eq!(quorum_required(&election(Quorum::Absolute(7), MajorityRule::Simple, 0)).unwrap(), 7);
+        assert_eq!(quorum_required(&election(Quorum::None, MajorityRule::Simple, 0)).unwrap(), 0);
+    }
+
+    #[test]
+    fn roll_share_quorum_requires_a_roll() {
+        assert!(quorum_required(&election(Quorum::RollShare(5_000), MajorityRule::Simple, 0)).is_err());
+        let mut result_data = result(&[3, 1], 4);
+        assert!(evaluate_outcome(&mut result_data, &election(Quorum::RollShare(5_000), MajorityRule::Simple, 0)).is_err());
+    }
+
+    #[test]
+    fn quorum_not_reached_has_no_winner() {
+        let mut result_data = result(&[3, 1], 4);
+        evaluate_outcome(&mut result_data, &election(Quorum::Absolute(5), MajorityRule::Simple, 0)).unwrap();
+        assert_eq!(result_data.outcome, ElectionOutcome::QuorumNotReached);
+        assert!(result_data.winner.is_empty());
+    }
+
+    #[test]
+    fn simple_majority_elects_the_single_leader() {
+        let mut result_data = result(&[4, 3, 3], 10);
+        evaluate_outcome(&mut result_data, &election(Quorum::None, MajorityRule::Simple, 0)).unwrap();
+        assert_eq!(result_data.outcome, ElectionOutcome::Valid);
+        assert_eq!(result_data.winner, "Candidato 0");
+
+        let mut result_data = result(&[4, 4, 2], 10);
+        evaluate_outcome(&mut result_data, &election(Quorum::None, MajorityRule::Simple, 0)).unwrap();
+        assert_eq!(result_data.outcome, ElectionOutcome::NoMajority);
+        assert!(result_data.runoff_required);
+    }
+
+    #[test]
+    fn absolute_majority_needs_more_than_half() {
+        let mut result_data = result(&[5, 5], 10);
+        evaluate_outcome(&mut result_data, &election(Quorum::None, MajorityRule::Absolute, 0)).unwrap();
+        assert_eq!(result_data.outcome, ElectionOutcome::NoMajority);
 
+        let mut result_data = result(&[6, 4], 10);
+        evaluate_outcome(&mut result_data, &election(Quorum::None, MajorityRule::Absolute, 0)).unwrap();
+        assert_eq!(result_data.outcome, ElectionOutcome::Valid);
+    }
+
+    #[test]
+    fn two_thirds_majority_includes_the_boundary() {
+        let mut result_data = result(&[6, 3], 9);
+        evaluate_outcome(&mut result_data, &election(Quorum::None, MajorityRule::TwoThirds, 0)).unwrap();
+        assert_eq!(result_data.outcome, ElectionOutcome::Valid);
+
+        let mut result_data = result(&[5, 3], 8);
+        evaluate_outcome(&mut result_data, &election(Quorum::None, MajorityRule::TwoThirds, 0)).unwrap();
+        assert_eq!(result_data.outcome, ElectionOutcome::NoMajority);
+    }
+
+    #[test]
+    fn majority_requires_more_than_half() {
+        assert!(!has_majority(5, 10));
+        assert!(has_majority(6, 10));
+        assert!(has_majority(3, 5));
+        assert!(!has_majority(0, 0));
+        assert!(!has_majority(1, -1));
+    }
 }
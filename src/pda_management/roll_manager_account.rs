@@ -0,0 +1,180 @@
+use solana_program::{
+    entrypoint::ProgramResult,
+    pubkey::Pubkey,
+    msg,
+    account_info::{next_account_info, AccountInfo},
+    system_instruction,
+    program_error::ProgramError,
+    sysvar::{rent::Rent, Sysvar},
+    program::invoke_signed
+};
+
+use crate::state::{account_header::ACCOUNT_HEADER_LEN, election_account_state::ElectionAccountState, roll_entry_state::RollEntryState};
+use crate::utilities::account_state_utilities::{load_account, save_account};
+
+//ISCRIVE UN VOTANTE NELLE LISTE ELETTORALI CON IL SUO PESO
+pub fn add_roll_entry(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    election_name: String,
+    electoral_card_number: String,
+    weight: u64,
+    voter: Pubkey
+) -> ProgramResult {
+
+    let seed = String::from("roll");
+
+    //CREA ITERATORE SU ACCOUNTS
+    let account_info_iter = &mut accounts.iter();
+
+    //RECUPERA ACCOUNT FORNITI DAL CLIENT
+    let initializer = next_account_info(account_info_iter)?;
+    let payer = next_account_info(account_info_iter)?;
+    let system_program = next_account_info(account_info_iter)?;
+    let election_pda_account = next_account_info(account_info_iter)?;
+    let roll_entry_pda_account = next_account_info(account_info_iter)?;
+
+    //DERIVA PDA
+    let (election_pda, _election_bump_seed) = Pubkey::find_program_address(
+        &[program_id.as_ref(), election_name.as_bytes()],
+        program_id
+    );
+    let (roll_entry_pda, roll_entry_bump_seed) = Pubkey::find_program_address(
+        &[program_id.as_ref(), election_name.as_bytes(), seed.as_bytes(), electoral_card_number.as_bytes()],
+        program_id
+    );
+
+    //VALIDAZIONE DEGLI ACCOUNT
+    if election_pda != *election_pda_account.key || roll_entry_pda != *roll_entry_pda_account.key {
+        msg!("Invalid seeds for PDA");
+        return Err(ProgramError::InvalidSeeds)
+    }
+    if election_pda_account.owner != program_id {
+        return Err(ProgramError::IllegalOwner)
+    }
+
+    let mut election_data = load_account::<ElectionAccountState>(election_pda_account)?;
+
+    if !initializer.is_signer || election_data.authority != *initializer.key {
+        msg!("Only the election authority can edit the electoral roll");
+        return Err(ProgramError::MissingRequiredSignature)
+    }
+    //IL BALLOTTAGGIO USA LE LISTE ELETTORALI DEL PRIMO TURNO
+    if !election_data.first_round.is_empty() {
+        msg!("The runoff uses the electoral roll of {}", election_data.first_round);
+        return Err(ProgramError::InvalidArgument)
+    }
+    if weight == 0 {
+        msg!("Weight must be positive");
+        return Err(ProgramError::InvalidArgument)
+    }
+
+    //CALCOLA DIMENSIONE DELL'ACCOUNT
+    let account_len: usize = ACCOUNT_HEADER_LEN + RollEntryState::space(&electoral_card_number);
+
+    //CALCOLA IL COSTO DI RENT
+    let rent = Rent::get()?;
+    let rent_lamports = rent.minimum_balance(account_len);
+
+    //CREA L'ACCOUNT
+    invoke_signed(
+        &system_instruction::create_account(
+            payer.key,
+            roll_entry_pda_account.key,
+            rent_lamports,
+            account_len.try_into().unwrap(),
+            program_id
+        ),
+        &[payer.clone(), roll_entry_pda_account.clone(), system_program.clone()],
+        &[&[program_id.as_ref(), election_name.as_bytes(), seed.as_bytes(), electoral_card_number.as_bytes(), &[roll_entry_bump_seed]]]
+    )?;
+
+    msg!("PDA Created: {}", roll_entry_pda);
+
+    let account_data = RollEntryState {
+        is_initialized: true,
+        electoral_card_number,
+        weight,
+        voter,
+    };
+    save_account(&account_data, roll_entry_pda_account)?;
+
+    //AGGIORNA LA DIMENSIONE DELLE LISTE ELETTORALI
+    election_data.roll_size += 1;
+    election_data.roll_weight = i64::try_from(weight)
+        .ok()
+        .and_then(|weight| election_data.roll_weight.checked_add(weight))
+        .ok_or(ProgramError::InvalidArgument)?;
+    save_account(&election_data, election_pda_account)?;
+
+    Ok(())
+}
+
+//NOME DELL'ELEZIONE CHE POSSIEDE LE LISTE ELETTORALI
+pub fn roll_election_name(
+    election_data: &ElectionAccountState
+) -> &str {
+    if election_data.first_round.is_empty() {
+        &election_data.name
+    } else {
+        &election_data.first_round
+    }
+}
+
+//VERIFICA L'ISCRIZIONE DEL VOTANTE E RESTITUISCE IL SUO PESO
+pub fn retrieve_roll_entry_weight(
+    program_id: &Pubkey,
+    roll_entry_pda_account: &AccountInfo,
+    roll_election_name: &str,
+    electoral_card_number: &str
+) -> Result<u64, ProgramError> {
+    Ok(retrieve_roll_entry(program_id, roll_entry_pda_account, roll_election_name, electoral_card_number)?.weight)
+}
+
+//VERIFICA CHE LA TESSERA SIA ISCRITTA E CHE IL SUO TITOLARE ABBIA FIRMATO
+pub fn check_roll_entry_signer(
+    program_id: &Pubkey,
+    roll_entry_pda_account: &AccountInfo,
+    roll_election_name: &str,
+    electoral_card_number: &str,
+    signer: &AccountInfo
+) -> Result<RollEntryState, ProgramError> {
+    let account_data = retrieve_roll_entry(program_id, roll_entry_pda_account, roll_election_name, electoral_card_number)?;
+
+    if !signer.is_signer || account_data.voter != *signer.key {
+        msg!("{} must be signed by the holder of card {}", signer.key, electoral_card_number);
+        return Err(ProgramError::MissingRequiredSignature)
+    }
+
+    Ok(account_data)
+}
+
+//LEGGE L'ISCRIZIONE DI UNA TESSERA NELLE LISTE ELETTORALI
+pub fn retrieve_roll_entry(
+    program_id: &Pubkey,
+    roll_entry_pda_account: &AccountInfo,
+    roll_election_name: &str,
+    electoral_card_number: &str
+) -> Result<RollEntryState, ProgramError> {
+    let (roll_entry_pda, _roll_entry_bump_seed) = Pubkey::find_program_address(
+        &[program_id.as_ref(), roll_election_name.as_bytes(), "roll".as_bytes(), electoral_card_number.as_bytes()],
+        program_id
+    );
+
+    if roll_entry_pda != *roll_entry_pda_account.key {
+        msg!("Invalid seeds for roll entry");
+        return Err(ProgramError::InvalidSeeds)
+    }
+    if roll_entry_pda_account.owner != program_id {
+        msg!("{} is not on the electoral roll", electoral_card_number);
+        return Err(ProgramError::IllegalOwner)
+    }
+
+    let account_data = load_account::<RollEntryState>(roll_entry_pda_account)?;
+
+    if !account_data.is_initialized {
+        return Err(ProgramError::UninitializedAccount)
+    }
+
+    Ok(account_data)
+}
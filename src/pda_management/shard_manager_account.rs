@@ -0,0 +1,231 @@
+use solana_program::{
+    entrypoint::ProgramResult,
+    pubkey::Pubkey,
+    msg,
+    account_info::{next_account_info, AccountInfo},
+    system_instruction,
+    program_error::ProgramError,
+    sysvar::{rent::Rent, Sysvar},
+    program::invoke_signed,
+    hash::hashv
+};
+
+use crate::{
+    state::{election_account_state::{ElectionAccountState, VotingMode}, shard_state::ShardState, vote_counters_state::VoteCountersHeader},
+    pda_management::{
+        election_manager_account::voting_started,
+        vote_counters_manager_account::{initialize_counters_header, check_counters_header, add_vote, merge_counters}
+    },
+    utilities::{account_size_utilities::realloc_with_rent, account_state_utilities::{load_account, save_account}}
+};
+
+//NUMERO MASSIMO DI CONTATORI PER ELEZIONE
+pub const MAX_SHARDS: u8 = 16;
+
+//Con i contatori distribuiti ogni voto scrive solo il contatore scelto dal hash del numero di tessera:
+//l'account elezione resta in sola lettura durante il voto e voti su contatori diversi non si contendono
+//il lock in scrittura. Ogni contatore ha il layout fisso dell'account contatori e lo scrutinio li somma.
+
+//DERIVA IL PDA DI UN CONTATORE
+pub fn find_shard_address(
+    program_id: &Pubkey,
+    election_name: &str,
+    index: u8
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[program_id.as_ref(), election_name.as_bytes(), "shard".as_bytes(), &[index]],
+        program_id
+    )
+}
+
+//SCEGLIE IL CONTATORE DEL VOTANTE DAL HASH DEL NUMERO DI TESSERA
+pub fn shard_index(
+    electoral_card_number: &str,
+    number_of_shards: u8
+) -> u8 {
+    let hash = hashv(&[electoral_card_number.as_bytes()]).to_bytes();
+    (u64::from_le_bytes(hash[..8].try_into().unwrap()) % number_of_shards as u64) as u8
+}
+
+//CREA I CONTATORI DISTRIBUITI DELL'ELEZIONE, PRIMA CHE INIZINO LE VOTAZIONI
+//Dopo questo passo la configurazione dell'elezione non puo' piu' cambiare
+pub fn add_vote_shards(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    election_name: String,
+    number_of_shards: u8
+) -> ProgramResult {
+
+    //CREA ITERATORE SU ACCOUNTS
+    let account_info_iter = &mut accounts.iter();
+
+    //RECUPERA ACCOUNT FORNITI DAL CLIENT
+    let initializer = next_account_info(account_info_iter)?;
+    let payer = next_account_info(account_info_iter)?;
+    let system_program = next_account_info(account_info_iter)?;
+    let election_pda_account = next_account_info(account_info_iter)?;
+
+    let (election_pda, _election_bump_seed) = Pubkey::find_program_address(
+        &[program_id.as_ref(), election_name.as_bytes()],
+        program_id
+    );
+    if election_pda != *election_pda_account.key {
+        msg!("Invalid seeds for PDA");
+        return Err(ProgramError::InvalidSeeds)
+    }
+    if election_pda_account.owner != program_id {
+        return Err(ProgramError::IllegalOwner)
+    }
+
+    let mut election_data = load_account::<ElectionAccountState>(election_pda_account)?;
+
+    if !initializer.is_signer || election_data.authority != *initializer.key {
+        msg!("Only the election authority can add vote shards");
+        return Err(ProgramError::MissingRequiredSignature)
+    }
+    if voting_started(&election_data)? {
+        msg!("Vote shards cannot be added after voting has started");
+        return Err(ProgramError::InvalidAccountData)
+    }
+    //SOLO VOTO PER TESTA O PONDERATO, SENZA CIRCOSCRIZIONI NE' SCHEDE SIGILLATE
+    if !matches!(election_data.voting_mode, VotingMode::Standard | VotingMode::Weighted) || election_data.number_of_districts > 0 || election_data.embargo {
+        msg!("Vote shards are not supported for election {}", election_name);
+        return Err(ProgramError::InvalidArgument)
+    }
+    if number_of_shards == 0 || number_of_shards > MAX_SHARDS {
+        msg!("Expected between 1 and {} shards", MAX_SHARDS);
+        return Err(ProgramError::InvalidArgument)
+    }
+
+    let rent = Rent::get()?;
+
+    for index in 0..number_of_shards {
+        let shard_pda_account = next_account_info(account_info_iter)?;
+        let (shard_pda, shard_bump_seed) = find_shard_address(program_id, &election_name, index);
+        if shard_pda != *shard_pda_account.key {
+            msg!("Invalid seeds for shard {}", index);
+            return Err(ProgramError::InvalidSeeds)
+        }
+
+        //UN CONTATORE PER OGNI CANDIDATO GIA' REGISTRATO, I SUCCESSIVI SI AGGIUNGONO CON REALLOC AL PRIMO VOTO
+        let account_len: usize = VoteCountersHeader::space(election_data.candidates.len());
+
+        invoke_signed(
+            &system_instruction::create_account(
+                payer.key,
+                shard_pda_account.key,
+                rent.minimum_balance(account_len),
+                account_len.try_into().unwrap(),
+                program_id
+            ),
+            &[payer.clone(), shard_pda_account.clone(), system_program.clone()],
+            &[&[program_id.as_ref(), election_name.as_bytes(), "shard".as_bytes(), &[index], &[shard_bump_seed]]]
+        )?;
+
+        initialize_counters_header(shard_pda_account, ShardState::DISCRIMINATOR, ShardState::VERSION)?;
+    }
+
+    msg!("{} vote shards for {}", number_of_shards, election_name);
+    election_data.number_of_shards = number_of_shards;
+    save_account(&election_data, election_pda_account)?;
+
+    Ok(())
+}
+
+pub fn validate_shard_account(
+    program_id: &Pubkey,
+    shard_pda_account: &AccountInfo,
+    election_name: &str,
+    index: u8
+) -> ProgramResult {
+    let (shard_pda, _shard_bump_seed) = find_shard_address(program_id, election_name, index);
+
+    if shard_pda != *shard_pda_account.key {
+        msg!("Invalid seeds for shard {}", index);
+        return Err(ProgramError::InvalidSeeds)
+    }
+    if shard_pda_account.owner != program_id {
+        return Err(ProgramError::IllegalOwner)
+    }
+
+    check_counters_header(shard_pda_account, ShardState::DISCRIMINATOR, ShardState::VERSION)
+}
+
+//REGISTRA UN VOTO NEL CONTATORE: AGGIORNA SOLO IL CONTATORE DEL CANDIDATO E L'INTESTAZIONE
+pub fn add_vote_to_shard<'a>(
+    shard_pda_account: &AccountInfo<'a>,
+    payer: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>,
+    slot: usize,
+    weight: i64
+) -> ProgramResult {
+    //IL CONTATORE CRESCE FINO ALL'INDICE DEL CANDIDATO VOTATO, I NUOVI BYTE SONO AZZERATI
+    realloc_with_rent(shard_pda_account, VoteCountersHeader::space(slot + 1), payer, system_program)?;
+
+    add_vote(shard_pda_account, slot, weight)
+}
+
+//SOMMA I CONTATORI, PASSATI IN ORDINE DI INDICE, NEI TOTALI IN MEMORIA DELL'ELEZIONE
+pub fn merge_shard_counts<'a, 'b: 'a>(
+    program_id: &Pubkey,
+    election_data: &mut ElectionAccountState,
+    account_info_iter: &mut impl Iterator<Item = &'a AccountInfo<'b>>
+) -> ProgramResult {
+    for index in 0..election_data.number_of_shards {
+        let shard_pda_account = next_account_info(account_info_iter)?;
+        validate_shard_account(program_id, shard_pda_account, &election_data.name, index)?;
+
+        merge_counters(election_data, shard_pda_account)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utilities::test_utilities::{TestAccount, fixed_counters};
+
+    #[test]
+    fn shard_votes_merge_into_the_election_totals() {
+        let program_id = Pubkey::new_unique();
+        let mut election_data = ElectionAccountState {
+            name: "Elettorale1".to_string(),
+            number_of_shards: 1,
+            ..Default::default()
+        };
+        election_data.push_candidate(Pubkey::new_unique());
+        election_data.push_candidate(Pubkey::new_unique());
+
+        let mut shard = TestAccount {
+            owner: program_id,
+            data: fixed_counters(ShardState::DISCRIMINATOR, ShardState::VERSION, 2),
+            ..TestAccount::empty(find_shard_address(&program_id, &election_data.name, 0).0)
+        };
+        let mut payer = TestAccount::signer(Pubkey::new_unique());
+        let mut system_program = TestAccount::empty(solana_program::system_program::id());
+        let (payer, system_program) = (payer.account_info(), system_program.account_info());
+        let shard = shard.account_info();
+
+        add_vote_to_shard(&shard, &payer, &system_program, 1, 3).unwrap();
+        add_vote_to_shard(&shard, &payer, &system_program, 0, 1).unwrap();
+        merge_shard_counts(&program_id, &mut election_data, &mut [shard].iter()).unwrap();
+
+        assert_eq!(election_data.votes, vec![1, 3]);
+        assert_eq!(election_data.headcount, vec![1, 1]);
+        assert_eq!(election_data.number_of_votes, 2);
+        assert_eq!(election_data.total_weight, 4);
+    }
+
+    #[test]
+    fn vote_counters_are_not_a_shard() {
+        let program_id = Pubkey::new_unique();
+        let mut shard = TestAccount {
+            owner: program_id,
+            data: fixed_counters(VoteCountersHeader::DISCRIMINATOR, VoteCountersHeader::VERSION, 1),
+            ..TestAccount::empty(find_shard_address(&program_id, "Elettorale1", 0).0)
+        };
+
+        assert_eq!(validate_shard_account(&program_id, &shard.account_info(), "Elettorale1", 0), Err(ProgramError::InvalidAccountData));
+    }
+}
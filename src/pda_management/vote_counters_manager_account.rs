@@ -0,0 +1,334 @@
+use solana_program::{
+    entrypoint::ProgramResult,
+    pubkey::Pubkey,
+    msg,
+    account_info::{next_account_info, AccountInfo},
+    system_instruction,
+    program_error::ProgramError,
+    sysvar::{rent::Rent, Sysvar},
+    program::invoke_signed
+};
+
+use bytemuck::Zeroable;
+
+use crate::{
+    state::{election_account_state::ElectionAccountState, vote_counters_state::{VoteCountersHeader, CandidateCounter}},
+    utilities::account_size_utilities::realloc_with_rent
+};
+
+//I voti per testa, ponderati e quadratici sono contati in un account a layout fisso accanto all'elezione:
+//ogni voto aggiorna solo il contatore del candidato e l'intestazione, senza deserializzare
+//ne' riserializzare l'account elezione. Lo scrutinio somma i contatori nei totali in memoria.
+
+//DERIVA IL PDA DEI CONTATORI DI UN'ELEZIONE
+pub fn find_vote_counters_address(
+    program_id: &Pubkey,
+    election_name: &str
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[program_id.as_ref(), election_name.as_bytes(), "counters".as_bytes()],
+        program_id
+    )
+}
+
+//CREA L'ACCOUNT CONTATORI VUOTO INSIEME ALL'ELEZIONE
+pub fn generate_vote_counters_account(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    election_name: String
+) -> ProgramResult {
+
+    let account_info_iter = &mut accounts.iter();
+
+    let _initializer = next_account_info(account_info_iter)?;
+    let payer = next_account_info(account_info_iter)?;
+    let system_program = next_account_info(account_info_iter)?;
+    let _election_pda_account = next_account_info(account_info_iter)?;
+    let _candidate_list_pda_account = next_account_info(account_info_iter)?;
+    let _result_pda_account = next_account_info(account_info_iter)?;
+    let vote_counters_pda_account = next_account_info(account_info_iter)?;
+
+    let (vote_counters_pda, vote_counters_bump_seed) = find_vote_counters_address(program_id, &election_name);
+    if vote_counters_pda != *vote_counters_pda_account.key {
+        msg!("Invalid seeds for vote counters");
+        return Err(ProgramError::InvalidSeeds)
+    }
+
+    //SOLO L'INTESTAZIONE: OGNI CANDIDATO AGGIUNGE IL SUO CONTATORE
+    let account_len: usize = VoteCountersHeader::space(0);
+    let rent = Rent::get()?;
+
+    invoke_signed(
+        &system_instruction::create_account(
+            payer.key,
+            vote_counters_pda_account.key,
+            rent.minimum_balance(account_len),
+            account_len.try_into().unwrap(),
+            program_id
+        ),
+        &[payer.clone(), vote_counters_pda_account.clone(), system_program.clone()],
+        &[&[program_id.as_ref(), election_name.as_bytes(), "counters".as_bytes(), &[vote_counters_bump_seed]]]
+    )?;
+
+    msg!("PDA Created: {}", vote_counters_pda);
+
+    initialize_counters_header(vote_counters_pda_account, VoteCountersHeader::DISCRIMINATOR, VoteCountersHeader::VERSION)
+}
+
+//SCRIVE DISCRIMINATORE E VERSIONE NELL'INTESTAZIONE DI UN ACCOUNT A LAYOUT FISSO APPENA CREATO
+pub fn initialize_counters_header(
+    counters_pda_account: &AccountInfo,
+    discriminator: [u8; 8],
+    version: u8
+) -> ProgramResult {
+    let mut data = counters_pda_account.data.borrow_mut();
+    let header = bytemuck::try_from_bytes_mut::<VoteCountersHeader>(data.get_mut(..VoteCountersHeader::LEN).ok_or(ProgramError::InvalidAccountData)?)
+        .map_err(|_| ProgramError::InvalidAccountData)?;
+    header.discriminator = discriminator;
+    header.version = version;
+
+    Ok(())
+}
+
+//VERIFICA DISCRIMINATORE E VERSIONE DI UN ACCOUNT A LAYOUT FISSO
+pub fn check_counters_header(
+    counters_pda_account: &AccountInfo,
+    discriminator: [u8; 8],
+    version: u8
+) -> ProgramResult {
+    let data = counters_pda_account.data.borrow();
+    let header = bytemuck::try_from_bytes::<VoteCountersHeader>(data.get(..VoteCountersHeader::LEN).ok_or(ProgramError::InvalidAccountData)?)
+        .map_err(|_| ProgramError::InvalidAccountData)?;
+    if header.discriminator != discriminator || header.version != version {
+        msg!("Account {} has an unexpected counters layout", counters_pda_account.key);
+        return Err(ProgramError::InvalidAccountData)
+    }
+
+    Ok(())
+}
+
+pub fn validate_vote_counters_account(
+    program_id: &Pubkey,
+    vote_counters_pda_account: &AccountInfo,
+    election_name: &str
+) -> ProgramResult {
+    let (vote_counters_pda, _vote_counters_bump_seed) = find_vote_counters_address(program_id, election_name);
+
+    if vote_counters_pda != *vote_counters_pda_account.key {
+        msg!("Invalid seeds for vote counters");
+        return Err(ProgramError::InvalidSeeds)
+    }
+    if vote_counters_pda_account.owner != program_id {
+        return Err(ProgramError::IllegalOwner)
+    }
+
+    check_counters_header(vote_counters_pda_account, VoteCountersHeader::DISCRIMINATOR, VoteCountersHeader::VERSION)
+}
+
+//AGGIUNGE IL CONTATORE DI UN NUOVO CANDIDATO IN CODA ALL'ACCOUNT
+pub fn add_candidate_counter<'a>(
+    vote_counters_pda_account: &AccountInfo<'a>,
+    payer: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>,
+    slot: usize
+) -> ProgramResult {
+    if vote_counters_pda_account.data_len() != CandidateCounter::offset(slot) {
+        msg!("Vote counters out of sync with the candidates");
+        return Err(ProgramError::InvalidAccountData)
+    }
+
+    realloc_with_rent(vote_counters_pda_account, VoteCountersHeader::space(slot + 1), payer, system_program)
+}
+
+//REGISTRA UN VOTO: AGGIORNA SOLO IL CONTATORE DEL CANDIDATO E L'INTESTAZIONE
+pub fn add_vote(
+    vote_counters_pda_account: &AccountInfo,
+    slot: usize,
+    weight: i64
+) -> ProgramResult {
+    let mut data = vote_counters_pda_account.data.borrow_mut();
+    let offset = CandidateCounter::offset(slot);
+    if data.len() < offset + CandidateCounter::LEN {
+        return Err(ProgramError::InvalidArgument)
+    }
+
+    let (header, counters) = data.split_at_mut(VoteCountersHeader::LEN);
+    let header = bytemuck::try_from_bytes_mut::<VoteCountersHeader>(header).map_err(|_| ProgramError::InvalidAccountData)?;
+    let counter = bytemuck::try_from_bytes_mut::<CandidateCounter>(&mut counters[offset - VoteCountersHeader::LEN..][..CandidateCounter::LEN])
+        .map_err(|_| ProgramError::InvalidAccountData)?;
+
+    counter.votes += weight;
+    counter.headcount += 1;
+    header.number_of_votes += 1;
+    header.total_weight += weight;
+
+    Ok(())
+}
+
+//REGISTRA n VOTI QUADRATICI PER UN CANDIDATO, PAGATI cost CREDITI
+//Il votante entra nell'affluenza al primo voto, nei votanti del candidato al primo voto per lui
+pub fn add_quadratic_votes(
+    vote_counters_pda_account: &AccountInfo,
+    slot: usize,
+    votes: u64,
+    cost: u64,
+    is_new_voter: bool,
+    is_new_supporter: bool
+) -> ProgramResult {
+    let votes = i64::try_from(votes).map_err(|_| ProgramError::InvalidArgument)?;
+    let cost = i64::try_from(cost).map_err(|_| ProgramError::InvalidArgument)?;
+
+    let mut data = vote_counters_pda_account.data.borrow_mut();
+    let offset = CandidateCounter::offset(slot);
+    if data.len() < offset + CandidateCounter::LEN {
+        return Err(ProgramError::InvalidArgument)
+    }
+
+    let (header, counters) = data.split_at_mut(VoteCountersHeader::LEN);
+    let header = bytemuck::try_from_bytes_mut::<VoteCountersHeader>(header).map_err(|_| ProgramError::InvalidAccountData)?;
+    let counter = bytemuck::try_from_bytes_mut::<CandidateCounter>(&mut counters[offset - VoteCountersHeader::LEN..][..CandidateCounter::LEN])
+        .map_err(|_| ProgramError::InvalidAccountData)?;
+
+    counter.votes += votes;
+    counter.credits += cost;
+    if is_new_supporter {
+        counter.headcount += 1;
+    }
+    if is_new_voter {
+        header.number_of_votes += 1;
+    }
+    header.total_weight += votes;
+
+    Ok(())
+}
+
+//REGISTRA UNA SCHEDA SIGILLATA: AGGIORNA SOLO L'AFFLUENZA NELL'INTESTAZIONE
+pub fn add_turnout(
+    vote_counters_pda_account: &AccountInfo,
+    weight: i64
+) -> ProgramResult {
+    let mut data = vote_counters_pda_account.data.borrow_mut();
+    let header = bytemuck::try_from_bytes_mut::<VoteCountersHeader>(data.get_mut(..VoteCountersHeader::LEN).ok_or(ProgramError::InvalidAccountData)?)
+        .map_err(|_| ProgramError::InvalidAccountData)?;
+
+    header.number_of_votes += 1;
+    header.total_weight += weight;
+
+    Ok(())
+}
+
+//RIVELA UNA SCHEDA SIGILLATA: AGGIORNA SOLO IL CONTATORE DEL CANDIDATO, L'AFFLUENZA E' GIA' CONTATA
+pub fn add_revealed_vote(
+    vote_counters_pda_account: &AccountInfo,
+    slot: usize,
+    weight: i64
+) -> ProgramResult {
+    let mut data = vote_counters_pda_account.data.borrow_mut();
+    let offset = CandidateCounter::offset(slot);
+    let counter = bytemuck::try_from_bytes_mut::<CandidateCounter>(data.get_mut(offset..offset + CandidateCounter::LEN).ok_or(ProgramError::InvalidArgument)?)
+        .map_err(|_| ProgramError::InvalidAccountData)?;
+
+    counter.votes += weight;
+    counter.headcount += 1;
+
+    Ok(())
+}
+
+//SOMMA I CONTATORI NEI TOTALI IN MEMORIA DELL'ELEZIONE
+pub fn merge_vote_counters(
+    program_id: &Pubkey,
+    election_data: &mut ElectionAccountState,
+    vote_counters_pda_account: &AccountInfo
+) -> ProgramResult {
+    validate_vote_counters_account(program_id, vote_counters_pda_account, &election_data.name)?;
+
+    if vote_counters_pda_account.data_len() != VoteCountersHeader::space(election_data.candidates.len()) {
+        msg!("Vote counters out of sync with the candidates");
+        return Err(ProgramError::InvalidAccountData)
+    }
+
+    merge_counters(election_data, vote_counters_pda_account)
+}
+
+//SOMMA UN ACCOUNT A LAYOUT FISSO NEI TOTALI IN MEMORIA DELL'ELEZIONE
+//L'account puo' avere meno contatori dei candidati, mai di piu'
+pub fn merge_counters(
+    election_data: &mut ElectionAccountState,
+    counters_pda_account: &AccountInfo
+) -> ProgramResult {
+    let data = counters_pda_account.data.borrow();
+    if data.len() < VoteCountersHeader::LEN || data.len() > VoteCountersHeader::space(election_data.candidates.len()) {
+        msg!("Counters {} out of sync with the candidates", counters_pda_account.key);
+        return Err(ProgramError::InvalidAccountData)
+    }
+
+    let header = bytemuck::try_from_bytes::<VoteCountersHeader>(&data[..VoteCountersHeader::LEN]).map_err(|_| ProgramError::InvalidAccountData)?;
+    let counters = bytemuck::try_cast_slice::<u8, CandidateCounter>(&data[VoteCountersHeader::LEN..]).map_err(|_| ProgramError::InvalidAccountData)?;
+
+    for (slot, counter) in counters.iter().enumerate() {
+        election_data.votes[slot] += counter.votes;
+        election_data.headcount[slot] += counter.headcount;
+        election_data.credits[slot] += counter.credits;
+    }
+    election_data.number_of_votes += header.number_of_votes;
+    election_data.total_weight += header.total_weight;
+
+    Ok(())
+}
+
+//LEGGE AFFLUENZA E PESO TOTALE DALL'INTESTAZIONE DI UN ACCOUNT A LAYOUT FISSO
+pub fn read_counters_totals(
+    counters_pda_account: &AccountInfo
+) -> Result<(i64, i64), ProgramError> {
+    let data = counters_pda_account.data.borrow();
+    let header = bytemuck::try_from_bytes::<VoteCountersHeader>(data.get(..VoteCountersHeader::LEN).ok_or(ProgramError::InvalidAccountData)?)
+        .map_err(|_| ProgramError::InvalidAccountData)?;
+
+    Ok((header.number_of_votes, header.total_weight))
+}
+
+//LEGGE IL CONTATORE DI UNA POSIZIONE SENZA LEGGERE GLI ALTRI, A ZERO SE L'ACCOUNT NON LO HA ANCORA
+pub fn read_candidate_counter(
+    counters_pda_account: &AccountInfo,
+    slot: usize
+) -> Result<CandidateCounter, ProgramError> {
+    let data = counters_pda_account.data.borrow();
+    let offset = CandidateCounter::offset(slot);
+    match data.get(offset..offset + CandidateCounter::LEN) {
+        Some(counter) => bytemuck::try_from_bytes::<CandidateCounter>(counter).copied().map_err(|_| ProgramError::InvalidAccountData),
+        None => Ok(CandidateCounter::zeroed()),
+    }
+}
+
+//POSIZIONE DEL CANDIDATO NELL'ACCOUNT CONTATORI
+pub fn candidate_slot(
+    election_data: &ElectionAccountState,
+    candidate_address: &Pubkey
+) -> Result<usize, ProgramError> {
+    election_data.candidates.iter().position(|candidate| candidate == candidate_address).ok_or(ProgramError::InvalidArgument)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utilities::test_utilities::{TestAccount, vote_counters};
+
+    #[test]
+    fn page_reads_see_only_the_requested_counter() {
+        let mut counters = TestAccount {
+            data: vote_counters(2),
+            ..TestAccount::empty(Pubkey::new_unique())
+        };
+        let counters = counters.account_info();
+
+        add_vote(&counters, 1, 3).unwrap();
+        add_quadratic_votes(&counters, 0, 2, 4, true, true).unwrap();
+
+        assert_eq!(read_counters_totals(&counters).unwrap(), (2, 5));
+        let counter = read_candidate_counter(&counters, 0).unwrap();
+        assert_eq!((counter.votes, counter.headcount, counter.credits), (2, 1, 4));
+        //UN CONTATORE DISTRIBUITO PIU' CORTO NON HA ANCORA VOTI PER I CANDIDATI SUCCESSIVI
+        let counter = read_candidate_counter(&counters, 5).unwrap();
+        assert_eq!((counter.votes, counter.headcount, counter.credits), (0, 0, 0));
+    }
+}
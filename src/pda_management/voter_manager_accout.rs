@@ -1,4 +1,3 @@
-use borsh::BorshSerialize;
 use solana_program::{
     entrypoint::ProgramResult,
     pubkey::Pubkey,
@@ -8,29 +7,176 @@ use solana_program::{
     program_error::ProgramError,
     sysvar::{rent::Rent, Sysvar},
     program::invoke_signed,
-    borsh0_10::try_from_slice_unchecked,
+    hash::hashv
 };
+use borsh::BorshSerialize;
 
 use crate::{
-    state::voter_account_state::VoterAccountState,
+    state::{account_header::ACCOUNT_HEADER_LEN, voter_account_state::VoterAccountState, relay_nonce_state::RelayNonceState, roll_entry_state::RollEntryState, election_account_state::{ElectionAccountState, VotingMode}},
      pda_management::{candidate_list_manager_account::retrieve_candidate_account,
-         election_manager_account::add_vote}
+         election_manager_account::check_voting_open,
+         vote_counters_manager_account::{add_vote, add_quadratic_votes, add_turnout, add_revealed_vote, candidate_slot, validate_vote_counters_account},
+         district_manager_account::{add_vote_to_district, validate_district_account},
+         roll_manager_account::{retrieve_roll_entry, check_roll_entry_signer, roll_election_name},
+         shard_manager_account::{shard_index, validate_shard_account, add_vote_to_shard}},
+    utilities::{election_account_utilities::{is_election_closed, is_retention_elapsed}, token_account_utilities::{transfer_tokens, unpack_token_account}, ed25519_utilities::{BallotMessage, ballot_message, verify_ed25519_signature}, account_state_utilities::{load_account, save_account}}
 };
 
+#[allow(clippy::too_many_arguments)]
 pub fn add_voter_account_and_vote (
     program_id: &Pubkey,
     accounts: &[AccountInfo],
     electoral_card_number: String,
-    candidate_first_name: String,
-    candidate_last_name: String,
+    candidate_index: u32,
     election_name: String,
-    seed: String
+    seed: String,
+    district_name: Option<String>
+) -> ProgramResult {
+
+    //Chi non paga deve comunque firmare
+    let initializer = accounts.first().ok_or(ProgramError::NotEnoughAccountKeys)?;
+    if !initializer.is_signer {
+        return Err(ProgramError::MissingRequiredSignature)
+    }
+
+    cast_vote(program_id, accounts, electoral_card_number, candidate_index, election_name, seed, district_name, None)
+}
+
+//Voto tramite relayer: il votante firma la scheda fuori catena con la sua chiave ed25519,
+//il relayer invia la transazione e paga gli account.
+//Account: sysvar instructions, nonce del votante, voce delle liste elettorali della tessera,
+//poi gli stessi account di AddVote con la chiave del votante (non firmataria) al posto dell'initializer
+//e il relayer come payer, senza ripetere la voce delle liste.
+//La voce delle liste lega la tessera alla chiave che firma la scheda.
+//L'istruzione precedente della transazione deve essere il precompile ed25519 sulla scheda.
+#[allow(clippy::too_many_arguments)]
+pub fn add_relayed_vote (
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    electoral_card_number: String,
+    candidate_index: u32,
+    election_name: String,
+    seed: String,
+    district_name: Option<String>,
+    nonce: u64
 ) -> ProgramResult {
 
     //Crea iteratore su accounts[]
     let account_info_iter = &mut accounts.iter();
     //Recupera account forniti da client
-    let initializer = next_account_info(account_info_iter)?;
+    let instructions_sysvar = next_account_info(account_info_iter)?;
+    let nonce_pda_account = next_account_info(account_info_iter)?;
+    let roll_entry_pda_account = next_account_info(account_info_iter)?;
+    let voter = next_account_info(account_info_iter)?;
+    let relayer = next_account_info(account_info_iter)?;
+    let _pda_account = next_account_info(account_info_iter)?;
+    let _candidate_list_pda_account = next_account_info(account_info_iter)?;
+    let election_pda_account = next_account_info(account_info_iter)?;
+    let system_program = next_account_info(account_info_iter)?;
+
+    let (election_pda, _election_bump_seed) = Pubkey::find_program_address(
+        &[program_id.as_ref(), election_name.as_bytes()],
+        program_id
+    );
+    if election_pda != *election_pda_account.key {
+        msg!("Invalid seed for account");
+        return Err(ProgramError::InvalidSeeds)
+    }
+    if election_pda_account.owner != program_id {
+        return Err(ProgramError::IllegalOwner)
+    }
+    let election_data = load_account::<ElectionAccountState>(election_pda_account)?;
+
+    //Solo il titolare della tessera puo' firmare la scheda: la sua chiave e' nelle liste elettorali
+    let roll_entry = retrieve_roll_entry(program_id, roll_entry_pda_account, roll_election_name(&election_data), &electoral_card_number)?;
+    if roll_entry.voter != *voter.key {
+        msg!("Card {} does not belong to {}", electoral_card_number, voter.key);
+        return Err(ProgramError::MissingRequiredSignature)
+    }
+
+    //Il nonce e' uno per chiave del votante, valido per tutte le elezioni
+    let seed_nonce = String::from("relay-nonce");
+    let (nonce_pda, nonce_bump_seed) = Pubkey::find_program_address(
+        &[program_id.as_ref(), seed_nonce.as_bytes(), voter.key.as_ref()],
+        program_id
+    );
+    if nonce_pda != *nonce_pda_account.key {
+        msg!("Invalid seed for nonce account");
+        return Err(ProgramError::InvalidSeeds)
+    }
+
+    //La firma copre elezione, tessera, candidato, circoscrizione e nonce
+    let message = ballot_message(&BallotMessage {
+        program_id: *program_id,
+        election_name: election_name.clone(),
+        electoral_card_number: electoral_card_number.clone(),
+        candidate_index,
+        district_name: district_name.clone(),
+        nonce
+    })?;
+    verify_ed25519_signature(instructions_sysvar, voter.key, &message)?;
+
+    //Alla prima scheda inoltrata crea l'account nonce a spese del relayer
+    let mut nonce_data = if nonce_pda_account.data_is_empty() {
+        let account_len: usize = ACCOUNT_HEADER_LEN + RelayNonceState::space();
+
+        let rent = Rent::get()?;
+        let rent_lamports = rent.minimum_balance(account_len);
+
+        invoke_signed(
+            &system_instruction::create_account(
+                relayer.key,
+                nonce_pda_account.key,
+                rent_lamports,
+                account_len.try_into().unwrap(),
+                program_id
+            ),
+            &[relayer.clone(), nonce_pda_account.clone(), system_program.clone()],
+            &[&[program_id.as_ref(), seed_nonce.as_bytes(), voter.key.as_ref(), &[nonce_bump_seed]]],
+        )?;
+
+        msg!("PDA Created: {}", nonce_pda);
+        RelayNonceState {
+            voter: *voter.key,
+            nonce: 0,
+        }
+    } else if nonce_pda_account.owner != program_id {
+        return Err(ProgramError::IllegalOwner)
+    } else {
+        load_account::<RelayNonceState>(nonce_pda_account)?
+    };
+
+    //Una scheda gia' inoltrata non puo' essere riutilizzata
+    if nonce != nonce_data.nonce {
+        msg!("Invalid nonce {}, expected {}", nonce, nonce_data.nonce);
+        return Err(ProgramError::InvalidArgument)
+    }
+    nonce_data.nonce = nonce_data.nonce.checked_add(1).ok_or(ProgramError::InvalidArgument)?;
+    save_account(&nonce_data, nonce_pda_account)?;
+
+    cast_vote(program_id, &accounts[3..], electoral_card_number, candidate_index, election_name, seed, district_name, Some(roll_entry))
+}
+
+//Crea l'account votante e registra il voto.
+//roll_entry e' la voce delle liste gia' legata alla scheda firmata fuori catena; senza di essa
+//la voce viene letta dagli account e il suo titolare deve firmare la transazione.
+#[allow(clippy::too_many_arguments)]
+fn cast_vote (
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    electoral_card_number: String,
+    candidate_index: u32,
+    election_name: String,
+    seed: String,
+    district_name: Option<String>,
+    roll_entry: Option<RollEntryState>
+) -> ProgramResult {
+
+    //Crea iteratore su accounts[]
+    let account_info_iter = &mut accounts.iter();
+    //Recupera account forniti da client
+    let voter = next_account_info(account_info_iter)?;
+    let payer = next_account_info(account_info_iter)?;
     let pda_account = next_account_info(account_info_iter)?;
     let candidate_list_pda_account = next_account_info(account_info_iter)?;
     let election_pda_account = next_account_info(account_info_iter)?;
@@ -38,17 +184,17 @@ pub fn add_voter_account_and_vote (
 
     //Deriva PDA
     let (pda, bump_seed) = Pubkey::find_program_address(
-        &[program_id.as_ref(), election_name.as_bytes().as_ref(), electoral_card_number.as_bytes().as_ref()],
+        &[program_id.as_ref(), election_name.as_bytes(), electoral_card_number.as_bytes()],
          program_id
         );
 
     let (candidate_pda, _candidate_bump_seed) = Pubkey::find_program_address(
-        &[program_id.as_ref(), election_name.as_bytes().as_ref(), seed.as_bytes().as_ref()],
+        &[program_id.as_ref(), election_name.as_bytes(), seed.as_bytes()],
             program_id
         );
 
     let (election_pda, _election_bump_seed) = Pubkey::find_program_address(
-         &[program_id.as_ref(), election_name.as_bytes().as_ref()],
+         &[program_id.as_ref(), election_name.as_bytes()],
             program_id
         );
     
@@ -57,9 +203,85 @@ pub fn add_voter_account_and_vote (
         msg!("Invalid seed for account");
         return Err(ProgramError::InvalidSeeds)
     }
+    if election_pda_account.owner != program_id {
+        return Err(ProgramError::IllegalOwner)
+    }
+
+    let election_data = load_account::<ElectionAccountState>(election_pda_account)?;
+
+    //Sotto embargo il candidato non deve comparire in chiaro ne' nell'istruzione ne' negli account
+    if election_data.embargo {
+        msg!("Election {} is under embargo, use AddSealedVote", election_name);
+        return Err(ProgramError::InvalidArgument)
+    }
+
+    //Se l'elezione e' divisa in circoscrizioni il voto deve indicarne una
+    let district_pda_account = match &district_name {
+        Some(district_name) => {
+            let district_pda_account = next_account_info(account_info_iter)?;
+            validate_district_account(program_id, district_pda_account, &election_name, district_name)?;
+            Some(district_pda_account)
+        }
+        None => {
+            if election_data.number_of_districts > 0 {
+                msg!("Election {} requires a district", election_name);
+                return Err(ProgramError::InvalidArgument)
+            }
+            None
+        }
+    };
+    let district_name = district_name.unwrap_or_default();
+
+    match election_data.voting_mode {
+        VotingMode::Standard | VotingMode::Weighted => {}
+        VotingMode::TokenWeighted => {
+            msg!("Election {} is token weighted, use AddTokenVote", election_name);
+            return Err(ProgramError::InvalidArgument)
+        }
+        VotingMode::Quadratic => {
+            msg!("Election {} is quadratic, use AddQuadraticVote", election_name);
+            return Err(ProgramError::InvalidArgument)
+        }
+    }
+
+    //La tessera deve essere nelle liste elettorali e il voto firmato dal suo titolare:
+    //nella transazione o, per la scheda inoltrata, fuori catena
+    let roll_entry = match roll_entry {
+        Some(roll_entry) => roll_entry,
+        None => {
+            let roll_entry_pda_account = next_account_info(account_info_iter)?;
+            check_roll_entry_signer(program_id, roll_entry_pda_account, roll_election_name(&election_data), &electoral_card_number, voter)?
+        }
+    };
+
+    //Nel voto ponderato il peso viene letto dalle liste elettorali
+    let weight: u64 = if election_data.voting_mode == VotingMode::Weighted { roll_entry.weight } else { 1 };
+
+    //Il voto scrive solo un contatore, non l'account elezione: il contatore distribuito del votante
+    //se l'elezione ne ha, altrimenti l'account contatori dell'elezione
+    check_voting_open(&election_data)?;
+    let counter_pda_account = next_account_info(account_info_iter)?;
+    if election_data.number_of_shards > 0 {
+        validate_shard_account(program_id, counter_pda_account, &election_name, shard_index(&electoral_card_number, election_data.number_of_shards))?;
+    } else {
+        validate_vote_counters_account(program_id, counter_pda_account, &election_name)?;
+    }
     
+    //Il candidato e il suo contatore si risolvono prima di creare l'account votante:
+    //un indice non valido non deve consumare la tessera
+    let candidate = match retrieve_candidate_account(candidate_list_pda_account, candidate_index) {
+        Ok(candidate) => candidate,
+        Err(error) => {
+            msg!("Error, invalid candidate {}", error);
+            return Err(ProgramError::InvalidAccountData)
+        }
+    };
+    let candidate_address = candidate.address;
+    //Ogni contatore usa l'indice del candidato nell'elezione
+    let slot = candidate_slot(&election_data, &candidate_address)?;
+
     //Calcola dimensione dell'account
-    let account_len: usize = 4 * electoral_card_number.len() + 32;
+    let account_len: usize = ACCOUNT_HEADER_LEN + VoterAccountState::space(&electoral_card_number, &district_name, 0);
 
     //Calcola costo di rent
     let rent = Rent::get()?;
@@ -68,62 +290,670 @@ pub fn add_voter_account_and_vote (
     //Crea account
     invoke_signed(
         &system_instruction::create_account(
-            initializer.key,
+            payer.key,
             pda_account.key,
             rent_lamports,
             account_len.try_into().unwrap(),
             program_id
         ),
-        &[initializer.clone(),pda_account.clone(), system_program.clone()],
-        &[&[program_id.as_ref(),election_name.as_bytes().as_ref(),electoral_card_number.as_bytes().as_ref(), &[bump_seed]]],
+        &[payer.clone(), pda_account.clone(), system_program.clone()],
+        &[&[program_id.as_ref(),election_name.as_bytes(),electoral_card_number.as_bytes(), &[bump_seed]]],
     )?;
 
     msg!("PDA Created: {}",pda);
 
-    match  retrieve_candidate_account( candidate_list_pda_account, candidate_first_name.clone(), candidate_last_name.clone()) {
-        Ok(candidate_address) => {
-
-            let is_voter_initialized = initialize_voter_account(pda_account, electoral_card_number, candidate_address);
-
-            if is_voter_initialized.is_ok() {
-                
-                let _ = add_vote(election_pda_account, candidate_address);
-                msg!("Hai votato {} {}", candidate_first_name, candidate_last_name);
-                return Ok(());
-                
-            } else {
-                return Err(ProgramError::InvalidSeeds);
-            }
+    if initialize_voter_account(pda_account, electoral_card_number, candidate_address, district_name, weight).is_err() {
+        return Err(ProgramError::InvalidSeeds);
+    }
 
-        }
-        Err(error)=> {
-            msg!("Error, invalid candidate {}",error);
-            return Err(ProgramError::InvalidAccountData)
-    
-        }
+    if election_data.number_of_shards > 0 {
+        add_vote_to_shard(counter_pda_account, payer, system_program, slot, weight as i64)?;
+    } else {
+        add_vote(counter_pda_account, slot, weight as i64)?;
     }
-}
+    if let Some(district_pda_account) = district_pda_account {
+        add_vote_to_district(district_pda_account, payer, system_program, slot, weight as i64)?;
+    }
+    msg!("Hai votato {}", candidate.name);
 
+    Ok(())
+}
 
 pub fn initialize_voter_account (
     pda_account: &AccountInfo,
     electoral_card_number: String,
-    candidate_address: Pubkey
+    candidate_address: Pubkey,
+    district_name: String,
+    weight: u64
 ) ->ProgramResult {
 
-    msg!("Unpacking voter account");
-    let mut account_data = try_from_slice_unchecked::<VoterAccountState>(&pda_account.data.borrow()).unwrap();
-    msg!("Borrowed account data");
-    
-    account_data.election_card_number = electoral_card_number;
-    account_data.voted = candidate_address;
+    //L'account e' appena stato creato: lo stato parte dai valori di default
+    let account_data = VoterAccountState {
+        election_card_number: electoral_card_number,
+        voted: candidate_address,
+        district: district_name,
+        weight,
+        ..Default::default()
+    };
 
 
     msg!("Serializing account");
-    account_data.serialize(&mut &mut pda_account.data.borrow_mut()[..])?;
+    save_account(&account_data, pda_account)?;
     msg!("Account serialized");
     
     Ok(())
 
     
-}
\ No newline at end of file
+}
+
+//Voto ponderato sul saldo del token: il saldo viene bloccato nel vault fino alla chiusura
+pub fn add_token_voter_and_vote (
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    candidate_index: u32,
+    election_name: String,
+    seed: String
+) -> ProgramResult {
+
+    //Crea iteratore su accounts[]
+    let account_info_iter = &mut accounts.iter();
+    //Recupera account forniti da client
+    let voter = next_account_info(account_info_iter)?;
+    let payer = next_account_info(account_info_iter)?;
+    let pda_account = next_account_info(account_info_iter)?;
+    let candidate_list_pda_account = next_account_info(account_info_iter)?;
+    let election_pda_account = next_account_info(account_info_iter)?;
+    let system_program = next_account_info(account_info_iter)?;
+    let voter_token_account = next_account_info(account_info_iter)?;
+    let vault_token_account = next_account_info(account_info_iter)?;
+    let token_program = next_account_info(account_info_iter)?;
+    let vote_counters_pda_account = next_account_info(account_info_iter)?;
+
+    if !voter.is_signer {
+        return Err(ProgramError::MissingRequiredSignature)
+    }
+
+    //Deriva PDA: un solo voto per wallet
+    let (pda, bump_seed) = Pubkey::find_program_address(
+        &[program_id.as_ref(), election_name.as_bytes(), voter.key.as_ref()],
+        program_id
+    );
+    let (candidate_pda, _candidate_bump_seed) = Pubkey::find_program_address(
+        &[program_id.as_ref(), election_name.as_bytes(), seed.as_bytes()],
+        program_id
+    );
+    let (election_pda, _election_bump_seed) = Pubkey::find_program_address(
+        &[program_id.as_ref(), election_name.as_bytes()],
+        program_id
+    );
+
+    if pda != *pda_account.key || candidate_pda != *candidate_list_pda_account.key || election_pda != *election_pda_account.key {
+        msg!("Invalid seed for account");
+        return Err(ProgramError::InvalidSeeds)
+    }
+    if election_pda_account.owner != program_id {
+        return Err(ProgramError::IllegalOwner)
+    }
+
+    let election_data = load_account::<ElectionAccountState>(election_pda_account)?;
+
+    if election_data.voting_mode != VotingMode::TokenWeighted {
+        msg!("Election {} is not token weighted", election_name);
+        return Err(ProgramError::InvalidArgument)
+    }
+    //Chiude anche alla data di fine: dopo, i token bloccati possono essere ritirati e non devono votare di nuovo
+    check_voting_open(&election_data)?;
+    validate_vote_counters_account(program_id, vote_counters_pda_account, &election_name)?;
+    if *vault_token_account.key != election_data.token_vault {
+        msg!("Invalid vault for election {}", election_name);
+        return Err(ProgramError::InvalidAccountData)
+    }
+
+    //Verifica proprietario e mint del token account e fotografa il saldo
+    let token_account = unpack_token_account(voter_token_account)?;
+    if token_account.owner != *voter.key || token_account.mint != election_data.mint {
+        msg!("Token account must hold {} and belong to the voter", election_data.mint);
+        return Err(ProgramError::InvalidAccountData)
+    }
+    if token_account.amount == 0 {
+        msg!("No tokens to vote with");
+        return Err(ProgramError::InsufficientFunds)
+    }
+    let weight = i64::try_from(token_account.amount).map_err(|_| ProgramError::InvalidArgument)?;
+
+    let candidate = retrieve_candidate_account(candidate_list_pda_account, candidate_index)?;
+    let candidate_address = candidate.address;
+
+    //Calcola dimensione dell'account
+    let account_len: usize = ACCOUNT_HEADER_LEN + VoterAccountState::space("", "", 0);
+
+    //Calcola costo di rent
+    let rent = Rent::get()?;
+    let rent_lamports = rent.minimum_balance(account_len);
+
+    //Crea account
+    invoke_signed(
+        &system_instruction::create_account(
+            payer.key,
+            pda_account.key,
+            rent_lamports,
+            account_len.try_into().unwrap(),
+            program_id
+        ),
+        &[payer.clone(), pda_account.clone(), system_program.clone()],
+        &[&[program_id.as_ref(), election_name.as_bytes(), voter.key.as_ref(), &[bump_seed]]],
+    )?;
+
+    msg!("PDA Created: {}", pda);
+
+    //Blocca i token nel vault: gli stessi token non possono votare due volte
+    transfer_tokens(token_program, voter_token_account, vault_token_account, voter, token_account.amount, &[])?;
+
+    let account_data = VoterAccountState {
+        voted: candidate_address,
+        weight: token_account.amount,
+        token_account: *voter_token_account.key,
+        locked_amount: token_account.amount,
+        ..Default::default()
+    };
+    save_account(&account_data, pda_account)?;
+
+    add_vote(vote_counters_pda_account, candidate_slot(&election_data, &candidate_address)?, weight)?;
+    msg!("Hai votato {} con {} token", candidate.name, token_account.amount);
+
+    Ok(())
+}
+
+//Restituisce i token bloccati al votante dopo la chiusura dell'elezione
+pub fn withdraw_locked_tokens (
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    election_name: String
+) -> ProgramResult {
+
+    let account_info_iter = &mut accounts.iter();
+
+    let voter = next_account_info(account_info_iter)?;
+    let pda_account = next_account_info(account_info_iter)?;
+    let election_pda_account = next_account_info(account_info_iter)?;
+    let voter_token_account = next_account_info(account_info_iter)?;
+    let vault_token_account = next_account_info(account_info_iter)?;
+    let vault_authority = next_account_info(account_info_iter)?;
+    let token_program = next_account_info(account_info_iter)?;
+
+    if !voter.is_signer {
+        return Err(ProgramError::MissingRequiredSignature)
+    }
+
+    let (pda, _bump_seed) = Pubkey::find_program_address(
+        &[program_id.as_ref(), election_name.as_bytes(), voter.key.as_ref()],
+        program_id
+    );
+    let (election_pda, _election_bump_seed) = Pubkey::find_program_address(
+        &[program_id.as_ref(), election_name.as_bytes()],
+        program_id
+    );
+    let (vault_pda, vault_bump_seed) = Pubkey::find_program_address(
+        &[program_id.as_ref(), election_name.as_bytes(), "vault".as_bytes()],
+        program_id
+    );
+
+    if pda != *pda_account.key || election_pda != *election_pda_account.key || vault_pda != *vault_authority.key {
+        msg!("Invalid seed for account");
+        return Err(ProgramError::InvalidSeeds)
+    }
+    if pda_account.owner != program_id || election_pda_account.owner != program_id {
+        return Err(ProgramError::IllegalOwner)
+    }
+
+    let election_data = load_account::<ElectionAccountState>(election_pda_account)?;
+
+    if !is_election_closed(&election_data.end_date)? {
+        msg!("Tokens stay locked until {}", election_data.end_date);
+        return Err(ProgramError::InvalidArgument)
+    }
+    if *vault_token_account.key != election_data.token_vault {
+        msg!("Invalid vault for election {}", election_name);
+        return Err(ProgramError::InvalidAccountData)
+    }
+
+    let mut account_data = load_account::<VoterAccountState>(pda_account)?;
+
+    if account_data.locked_amount == 0 || account_data.token_account != *voter_token_account.key {
+        msg!("Nothing to withdraw to {}", voter_token_account.key);
+        return Err(ProgramError::InvalidAccountData)
+    }
+
+    transfer_tokens(
+        token_program,
+        vault_token_account,
+        voter_token_account,
+        vault_authority,
+        account_data.locked_amount,
+        &[&[program_id.as_ref(), election_name.as_bytes(), "vault".as_bytes(), &[vault_bump_seed]]]
+    )?;
+
+    msg!("Restituiti {} token", account_data.locked_amount);
+    account_data.locked_amount = 0;
+    save_account(&account_data, pda_account)?;
+
+    Ok(())
+}
+
+//Voto quadratico: n voti a un candidato costano n^2 crediti, sottratti dal budget del votante
+//La tessera deve essere iscritta nelle liste elettorali: l'account dell'iscrizione segue il system program,
+//poi l'account contatori dell'elezione, l'unico scritto dal voto oltre all'account votante
+#[allow(clippy::too_many_arguments)]
+pub fn add_quadratic_vote (
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    electoral_card_number: String,
+    candidate_index: u32,
+    election_name: String,
+    seed: String,
+    votes: u64
+) -> ProgramResult {
+
+    //Crea iteratore su accounts[]
+    let account_info_iter = &mut accounts.iter();
+    //Recupera account forniti da client
+    let initializer = next_account_info(account_info_iter)?;
+    let payer = next_account_info(account_info_iter)?;
+    let pda_account = next_account_info(account_info_iter)?;
+    let candidate_list_pda_account = next_account_info(account_info_iter)?;
+    let election_pda_account = next_account_info(account_info_iter)?;
+    let system_program = next_account_info(account_info_iter)?;
+    let roll_entry_pda_account = next_account_info(account_info_iter)?;
+    let vote_counters_pda_account = next_account_info(account_info_iter)?;
+
+    //Chi non paga deve comunque firmare
+    if !initializer.is_signer {
+        return Err(ProgramError::MissingRequiredSignature)
+    }
+
+    //Deriva PDA
+    let (pda, bump_seed) = Pubkey::find_program_address(
+        &[program_id.as_ref(), election_name.as_bytes(), electoral_card_number.as_bytes()],
+        program_id
+    );
+    let (candidate_pda, _candidate_bump_seed) = Pubkey::find_program_address(
+        &[program_id.as_ref(), election_name.as_bytes(), seed.as_bytes()],
+        program_id
+    );
+    let (election_pda, _election_bump_seed) = Pubkey::find_program_address(
+        &[program_id.as_ref(), election_name.as_bytes()],
+        program_id
+    );
+
+    if pda != *pda_account.key || candidate_pda != *candidate_list_pda_account.key || election_pda != *election_pda_account.key {
+        msg!("Invalid seed for account");
+        return Err(ProgramError::InvalidSeeds)
+    }
+    if election_pda_account.owner != program_id {
+        return Err(ProgramError::IllegalOwner)
+    }
+
+    let election_data = load_account::<ElectionAccountState>(election_pda_account)?;
+
+    if election_data.voting_mode != VotingMode::Quadratic {
+        msg!("Election {} is not quadratic", election_name);
+        return Err(ProgramError::InvalidArgument)
+    }
+    check_voting_open(&election_data)?;
+    //Il budget di crediti spetta solo alle tessere iscritte nelle liste elettorali, spese dal loro titolare
+    check_roll_entry_signer(program_id, roll_entry_pda_account, roll_election_name(&election_data), &electoral_card_number, initializer)?;
+    validate_vote_counters_account(program_id, vote_counters_pda_account, &election_name)?;
+    if votes == 0 {
+        return Err(ProgramError::InvalidArgument)
+    }
+
+    let candidate = retrieve_candidate_account(candidate_list_pda_account, candidate_index)?;
+    let candidate_address = candidate.address;
+    let slot = candidate_slot(&election_data, &candidate_address)?;
+
+    //Al primo voto crea l'account con il budget di crediti dell'elezione
+    let is_new_voter = pda_account.data_is_empty();
+    if is_new_voter {
+        //Spazio per un'allocazione per ogni candidato dell'elezione
+        let account_len: usize = ACCOUNT_HEADER_LEN + VoterAccountState::space(&electoral_card_number, "", election_data.candidates.len());
+
+        let rent = Rent::get()?;
+        let rent_lamports = rent.minimum_balance(account_len);
+
+        invoke_signed(
+            &system_instruction::create_account(
+                payer.key,
+                pda_account.key,
+                rent_lamports,
+                account_len.try_into().unwrap(),
+                program_id
+            ),
+            &[payer.clone(), pda_account.clone(), system_program.clone()],
+            &[&[program_id.as_ref(), election_name.as_bytes(), electoral_card_number.as_bytes(), &[bump_seed]]],
+        )?;
+
+        msg!("PDA Created: {}", pda);
+    } else if pda_account.owner != program_id {
+        return Err(ProgramError::IllegalOwner)
+    }
+
+    let mut account_data = if is_new_voter {
+        VoterAccountState {
+            election_card_number: electoral_card_number,
+            voted: candidate_address,
+            remaining_credits: election_data.voice_credits,
+            ..Default::default()
+        }
+    } else {
+        load_account::<VoterAccountState>(pda_account)?
+    };
+
+    //Costo marginale: da k a k+n voti il costo passa da k^2 a (k+n)^2
+    let allocated = account_data.allocations.get(&(slot as u32)).copied().unwrap_or(0);
+    let new_allocation = allocated.checked_add(votes).ok_or(ProgramError::InvalidArgument)?;
+    let cost = new_allocation
+        .checked_mul(new_allocation)
+        .and_then(|total| total.checked_sub(allocated * allocated))
+        .ok_or(ProgramError::InvalidArgument)?;
+
+    if cost > account_data.remaining_credits {
+        msg!("{} votes cost {} credits, only {} left", votes, cost, account_data.remaining_credits);
+        return Err(ProgramError::InsufficientFunds)
+    }
+
+    account_data.remaining_credits -= cost;
+    account_data.allocations.insert(slot as u32, new_allocation);
+    save_account(&account_data, pda_account)?;
+
+    //Come gli altri voti, anche il voto quadratico scrive solo l'account contatori
+    add_quadratic_votes(vote_counters_pda_account, slot, votes, cost, is_new_voter, allocated == 0)?;
+    msg!("Hai dato {} voti a {} ({} crediti)", votes, candidate.name, cost);
+
+    Ok(())
+}
+
+//Finestra dopo la chiusura in cui le schede sigillate possono essere rivelate
+pub const BALLOT_REVEAL_WINDOW_SECONDS: i64 = 3 * 24 * 60 * 60;
+
+//Impegno di una scheda sigillata: hash della serializzazione Borsh di elezione, tessera,
+//indice del candidato e sale scelto dal votante
+pub fn sealed_ballot_commitment(
+    election_name: &str,
+    electoral_card_number: &str,
+    candidate_index: u32,
+    salt: &[u8; 32]
+) -> Result<[u8; 32], ProgramError> {
+    let ballot = (election_name, electoral_card_number, candidate_index, salt).try_to_vec()?;
+    Ok(hashv(&[&ballot]).to_bytes())
+}
+
+//Voto sotto embargo: registra solo l'impegno della scheda e l'affluenza.
+//Il candidato non compare ne' nell'istruzione ne' negli account fino alla rivelazione dopo la chiusura,
+//quindi i conteggi per candidato restano vuoti sulla chain per tutta la durata del voto.
+//Account: initializer, payer, account votante, elezione, system program, circoscrizione se indicata,
+//voce delle liste elettorali della tessera, account contatori.
+pub fn add_sealed_vote (
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    electoral_card_number: String,
+    election_name: String,
+    district_name: Option<String>,
+    commitment: [u8; 32]
+) -> ProgramResult {
+
+    //Crea iteratore su accounts[]
+    let account_info_iter = &mut accounts.iter();
+    //Recupera account forniti da client
+    let initializer = next_account_info(account_info_iter)?;
+    let payer = next_account_info(account_info_iter)?;
+    let pda_account = next_account_info(account_info_iter)?;
+    let election_pda_account = next_account_info(account_info_iter)?;
+    let system_program = next_account_info(account_info_iter)?;
+
+    if !initializer.is_signer {
+        return Err(ProgramError::MissingRequiredSignature)
+    }
+
+    let (pda, bump_seed) = Pubkey::find_program_address(
+        &[program_id.as_ref(), election_name.as_bytes(), electoral_card_number.as_bytes()],
+        program_id
+    );
+    let (election_pda, _election_bump_seed) = Pubkey::find_program_address(
+        &[program_id.as_ref(), election_name.as_bytes()],
+        program_id
+    );
+    if election_pda != *election_pda_account.key {
+        msg!("Invalid seed for account");
+        return Err(ProgramError::InvalidSeeds)
+    }
+    if election_pda_account.owner != program_id {
+        return Err(ProgramError::IllegalOwner)
+    }
+
+    let election_data = load_account::<ElectionAccountState>(election_pda_account)?;
+
+    if !election_data.embargo {
+        msg!("Election {} is not under embargo, use AddVote", election_name);
+        return Err(ProgramError::InvalidArgument)
+    }
+    check_voting_open(&election_data)?;
+
+    //La circoscrizione e' pubblica come l'affluenza, il candidato no
+    match &district_name {
+        Some(district_name) => {
+            let district_pda_account = next_account_info(account_info_iter)?;
+            validate_district_account(program_id, district_pda_account, &election_name, district_name)?;
+        }
+        None => {
+            if election_data.number_of_districts > 0 {
+                msg!("Election {} requires a district", election_name);
+                return Err(ProgramError::InvalidArgument)
+            }
+        }
+    }
+    let district_name = district_name.unwrap_or_default();
+
+    //L'embargo e' ammesso solo per il voto per testa e ponderato
+    if !matches!(election_data.voting_mode, VotingMode::Standard | VotingMode::Weighted) {
+        msg!("Sealed ballots are not supported for election {}", election_name);
+        return Err(ProgramError::InvalidArgument)
+    }
+
+    //Solo il titolare della tessera iscritta nelle liste puo' depositare la scheda
+    let roll_entry_pda_account = next_account_info(account_info_iter)?;
+    let roll_entry = check_roll_entry_signer(program_id, roll_entry_pda_account, roll_election_name(&election_data), &electoral_card_number, initializer)?;
+    let weight: u64 = if election_data.voting_mode == VotingMode::Weighted { roll_entry.weight } else { 1 };
+
+    let counter_pda_account = next_account_info(account_info_iter)?;
+    validate_vote_counters_account(program_id, counter_pda_account, &election_name)?;
+
+    let account_len: usize = ACCOUNT_HEADER_LEN + VoterAccountState::space(&electoral_card_number, &district_name, 0);
+    let rent = Rent::get()?;
+
+    //La creazione fallisce se la tessera ha gia' votato
+    invoke_signed(
+        &system_instruction::create_account(
+            payer.key,
+            pda_account.key,
+            rent.minimum_balance(account_len),
+            account_len.try_into().unwrap(),
+            program_id
+        ),
+        &[payer.clone(), pda_account.clone(), system_program.clone()],
+        &[&[program_id.as_ref(), election_name.as_bytes(), electoral_card_number.as_bytes(), &[bump_seed]]],
+    )?;
+
+    msg!("PDA Created: {}", pda);
+
+    let account_data = VoterAccountState {
+        election_card_number: electoral_card_number,
+        sealed_ballot: commitment,
+        district: district_name,
+        weight,
+        ..Default::default()
+    };
+    save_account(&account_data, pda_account)?;
+
+    add_turnout(counter_pda_account, weight as i64)?;
+    msg!("Scheda sigillata registrata");
+
+    Ok(())
+}
+
+//Rivela una scheda sigillata dopo la chiusura, entro BALLOT_REVEAL_WINDOW_SECONDS.
+//Chiunque conosca il sale puo' rivelarla: l'impegno vincola elezione, tessera e candidato.
+//Le schede non rivelate restano nell'affluenza ma non vanno a nessun candidato, come le schede bianche.
+//Account: payer, account votante, elezione, system program, account contatori, circoscrizione del votante se presente.
+pub fn reveal_sealed_vote (
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    electoral_card_number: String,
+    election_name: String,
+    candidate_index: u32,
+    salt: [u8; 32]
+) -> ProgramResult {
+
+    //Crea iteratore su accounts[]
+    let account_info_iter = &mut accounts.iter();
+    //Recupera account forniti da client
+    let payer = next_account_info(account_info_iter)?;
+    let pda_account = next_account_info(account_info_iter)?;
+    let election_pda_account = next_account_info(account_info_iter)?;
+    let system_program = next_account_info(account_info_iter)?;
+    let counter_pda_account = next_account_info(account_info_iter)?;
+
+    let (pda, _bump_seed) = Pubkey::find_program_address(
+        &[program_id.as_ref(), election_name.as_bytes(), electoral_card_number.as_bytes()],
+        program_id
+    );
+    let (election_pda, _election_bump_seed) = Pubkey::find_program_address(
+        &[program_id.as_ref(), election_name.as_bytes()],
+        program_id
+    );
+    if pda != *pda_account.key || election_pda != *election_pda_account.key {
+        msg!("Invalid seed for account");
+        return Err(ProgramError::InvalidSeeds)
+    }
+    if pda_account.owner != program_id || election_pda_account.owner != program_id {
+        return Err(ProgramError::IllegalOwner)
+    }
+
+    let election_data = load_account::<ElectionAccountState>(election_pda_account)?;
+    if !is_election_closed(&election_data.end_date)? {
+        msg!("Sealed ballots are revealed after {}", election_data.end_date);
+        return Err(ProgramError::InvalidAccountData)
+    }
+    if is_retention_elapsed(&election_data.end_date, BALLOT_REVEAL_WINDOW_SECONDS)? {
+        msg!("The reveal window for {} has ended", election_name);
+        return Err(ProgramError::InvalidAccountData)
+    }
+    validate_vote_counters_account(program_id, counter_pda_account, &election_name)?;
+
+    let mut account_data = load_account::<VoterAccountState>(pda_account)?;
+    if account_data.voted != Pubkey::default() {
+        msg!("Ballot of {} already revealed", electoral_card_number);
+        return Err(ProgramError::AccountAlreadyInitialized)
+    }
+    if sealed_ballot_commitment(&election_name, &electoral_card_number, candidate_index, &salt)? != account_data.sealed_ballot {
+        msg!("Ballot does not match the sealed commitment");
+        return Err(ProgramError::InvalidArgument)
+    }
+
+    let slot = candidate_index as usize;
+    let candidate_address = *election_data.candidates.get(slot).ok_or(ProgramError::InvalidArgument)?;
+
+    let weight = account_data.weight as i64;
+    add_revealed_vote(counter_pda_account, slot, weight)?;
+    if !account_data.district.is_empty() {
+        let district_pda_account = next_account_info(account_info_iter)?;
+        validate_district_account(program_id, district_pda_account, &election_name, &account_data.district)?;
+        add_vote_to_district(district_pda_account, payer, system_program, slot, weight)?;
+    }
+
+    account_data.voted = candidate_address;
+    save_account(&account_data, pda_account)?;
+    msg!("Scheda di {} rivelata", electoral_card_number);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{state::candidate_list_state::{CandidateListState, CandidateEntry}, utilities::test_utilities::{TestAccount, account_infos, vote_counters, setup_syscall_stubs, invoked_instructions}};
+
+    const ELECTION: &str = "Elettorale1";
+    const CARD: &str = "AA1234567";
+    const SEED: &str = "candidate-list";
+
+    fn address(program_id: &Pubkey, seeds: &[&[u8]]) -> Pubkey {
+        let seeds: Vec<&[u8]> = [program_id.as_ref()].into_iter().chain(seeds.iter().copied()).collect();
+        Pubkey::find_program_address(&seeds, program_id).0
+    }
+
+    //Account di AddVote per la tessera CARD iscritta con la chiave holder, firmati da signer
+    fn vote_accounts(program_id: &Pubkey, signer: Pubkey, holder: Pubkey) -> Vec<TestAccount> {
+        let candidate = Pubkey::new_unique();
+        let election_data = ElectionAccountState {
+            is_initialized: true,
+            name: ELECTION.to_string(),
+            start_date: "2023-01-01 00:00:00".to_string(),
+            end_date: "2030-01-01 00:00:00".to_string(),
+            votes: vec![0],
+            headcount: vec![0],
+            candidates: vec![candidate],
+            ..Default::default()
+        };
+        let candidate_list = CandidateListState {
+            is_initialized: true,
+            candidates: vec![CandidateEntry {
+                name: "Marco Togni".to_string(),
+                address: candidate,
+                ..Default::default()
+            }],
+        };
+        let roll_entry = RollEntryState {
+            is_initialized: true,
+            electoral_card_number: CARD.to_string(),
+            weight: 1,
+            voter: holder,
+        };
+        vec![
+            TestAccount::signer(signer),
+            TestAccount::signer(signer),
+            TestAccount::empty(address(program_id, &[ELECTION.as_bytes(), CARD.as_bytes()])),
+            TestAccount::with_state(program_id, address(program_id, &[ELECTION.as_bytes(), SEED.as_bytes()]), &candidate_list),
+            TestAccount::with_state(program_id, address(program_id, &[ELECTION.as_bytes()]), &election_data),
+            TestAccount::empty(solana_program::system_program::id()),
+            TestAccount::with_state(program_id, address(program_id, &[ELECTION.as_bytes(), b"roll", CARD.as_bytes()]), &roll_entry),
+            TestAccount {
+                owner: *program_id,
+                data: vote_counters(1),
+                ..TestAccount::empty(address(program_id, &[ELECTION.as_bytes(), b"counters"]))
+            },
+        ]
+    }
+
+    #[test]
+    fn vote_signed_by_another_key_is_rejected() {
+        let program_id = Pubkey::new_unique();
+        let mut accounts = vote_accounts(&program_id, Pubkey::new_unique(), Pubkey::new_unique());
+        let result = add_voter_account_and_vote(&program_id, &account_infos(&mut accounts), CARD.to_string(), 0, ELECTION.to_string(), SEED.to_string(), None);
+
+        assert_eq!(result, Err(ProgramError::MissingRequiredSignature));
+        assert!(accounts[2].data.is_empty());
+    }
+
+    #[test]
+    fn invalid_candidate_leaves_the_card_unused() {
+        setup_syscall_stubs();
+        let program_id = Pubkey::new_unique();
+        let holder = Pubkey::new_unique();
+        let mut accounts = vote_accounts(&program_id, holder, holder);
+        let result = add_voter_account_and_vote(&program_id, &account_infos(&mut accounts), CARD.to_string(), 1, ELECTION.to_string(), SEED.to_string(), None);
+
+        assert_eq!(result, Err(ProgramError::InvalidAccountData));
+        assert!(invoked_instructions().is_empty());
+        assert!(accounts[2].data.is_empty());
+    }
+}
@@ -0,0 +1,179 @@
+use borsh::{BorshSerialize, BorshDeserialize};
+use solana_program::{borsh0_10::try_from_slice_unchecked, msg, program_error::ProgramError};
+
+//INTESTAZIONE DI OGNI ACCOUNT DEL PROGRAMMA: DISCRIMINATORE (8 BYTE) + VERSIONE (1 BYTE)
+//Il discriminatore sono i primi 8 byte di sha256("account:<NomeTipo>"), come in Anchor,
+//cosi' un account di un tipo non puo' essere letto come un altro. Lo stato Borsh segue l'intestazione.
+pub const DISCRIMINATOR_LEN: usize = 8;
+pub const ACCOUNT_HEADER_LEN: usize = DISCRIMINATOR_LEN + 1;
+
+pub trait AccountState: BorshSerialize + BorshDeserialize {
+    const DISCRIMINATOR: [u8; DISCRIMINATOR_LEN];
+    //VERSIONE CORRENTE DEL LAYOUT, SCRITTA A OGNI SALVATAGGIO
+    const VERSION: u8;
+
+    fn header() -> [u8; ACCOUNT_HEADER_LEN] {
+        let mut header = [0; ACCOUNT_HEADER_LEN];
+        header[..DISCRIMINATOR_LEN].copy_from_slice(&Self::DISCRIMINATOR);
+        header[DISCRIMINATOR_LEN] = Self::VERSION;
+        header
+    }
+
+    //CONVERTE LO STATO DI UNA VERSIONE PRECEDENTE NEL LAYOUT CORRENTE
+    //La versione 0 e' l'account creato prima dell'intestazione, con il layout dei tipi in legacy:
+    //solo i tipi che esistevano allora la convertono, per gli altri non esiste e viene rifiutata.
+    //Un tipo che cambia layout alza VERSION e ridefinisce upgrade per le versioni precedenti.
+    fn upgrade(version: u8, _data: &[u8]) -> Result<Self, ProgramError> {
+        msg!("No conversion from version {} to {}", version, Self::VERSION);
+        Err(ProgramError::InvalidAccountData)
+    }
+}
+
+//LEGGE UN ACCOUNT DI VERSIONE 0 CON IL SUO LAYOUT ORIGINALE, IGNORANDO I BYTE LIBERI IN CODA
+pub fn read_legacy<T: BorshDeserialize>(
+    data: &[u8]
+) -> Result<T, ProgramError> {
+    try_from_slice_unchecked::<T>(data).map_err(|_| ProgramError::InvalidAccountData)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use solana_program::{hash::hash, pubkey::Pubkey};
+
+    use super::*;
+    use crate::state::{
+        legacy, candidate_list_state::CandidateListState, candidate_state::CandidateState, delegation_state::{DelegationState, DelegationCounterState},
+        district_state::DistrictState, election_account_state::ElectionAccountState, result_state::ResultState,
+        roll_entry_state::RollEntryState, shard_state::ShardState, relay_nonce_state::RelayNonceState, vote_counters_state::VoteCountersHeader,
+        voter_account_state::VoterAccountState
+    };
+
+    fn expected(type_name: &str) -> [u8; DISCRIMINATOR_LEN] {
+        hash(format!("account:{}", type_name).as_bytes()).to_bytes()[..DISCRIMINATOR_LEN].try_into().unwrap()
+    }
+
+    #[test]
+    fn discriminators_match_type_names() {
+        assert_eq!(ElectionAccountState::DISCRIMINATOR, expected("ElectionAccountState"));
+        assert_eq!(CandidateListState::DISCRIMINATOR, expected("CandidateListState"));
+        assert_eq!(CandidateState::DISCRIMINATOR, expected("CandidateState"));
+        assert_eq!(VoterAccountState::DISCRIMINATOR, expected("VoterAccountState"));
+        assert_eq!(ResultState::DISCRIMINATOR, expected("ResultState"));
+        assert_eq!(DistrictState::DISCRIMINATOR, expected("DistrictState"));
+        assert_eq!(RollEntryState::DISCRIMINATOR, expected("RollEntryState"));
+        assert_eq!(DelegationState::DISCRIMINATOR, expected("DelegationState"));
+        assert_eq!(DelegationCounterState::DISCRIMINATOR, expected("DelegationCounterState"));
+        assert_eq!(ShardState::DISCRIMINATOR, expected("ShardState"));
+        assert_eq!(VoteCountersHeader::DISCRIMINATOR, expected("VoteCounters"));
+        assert_eq!(RelayNonceState::DISCRIMINATOR, expected("RelayNonceState"));
+    }
+    #[test]
+    fn legacy_election_converts_to_current_layout() {
+        let (first, second) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let legacy = legacy::ElectionAccountState {
+            is_initialized: true,
+            name: "Elettorale1".to_string(),
+            start_date: "2023-10-01 08:00:00".to_string(),
+            end_date: "2023-10-02 20:00:00".to_string(),
+            votes: HashMap::from([(first, 3), (second, 1)]),
+            number_of_votes: 4,
+            is_active: true,
+        };
+        //GLI ACCOUNT DI VERSIONE 0 ERANO ALLOCATI CON SPAZIO LIBERO IN CODA
+        let mut data = legacy.try_to_vec().unwrap();
+        data.resize(data.len() + 64, 0);
+
+        let upgraded = ElectionAccountState::upgrade(0, &data).unwrap();
+        let mut expected_candidates = vec![first, second];
+        expected_candidates.sort();
+        let expected_votes: Vec<i64> = expected_candidates.iter().map(|candidate| if *candidate == first { 3 } else { 1 }).collect();
+
+        assert!(upgraded.is_initialized && upgraded.is_active);
+        assert_eq!(upgraded.name, "Elettorale1");
+        assert_eq!(upgraded.start_date, "2023-10-01 08:00:00");
+        assert_eq!(upgraded.end_date, "2023-10-02 20:00:00");
+        assert_eq!(upgraded.candidates, expected_candidates);
+        assert_eq!(upgraded.votes, expected_votes);
+        assert_eq!(upgraded.headcount, expected_votes);
+        assert_eq!(upgraded.credits, vec![0, 0]);
+        assert_eq!(upgraded.number_of_votes, 4);
+        assert_eq!(upgraded.total_weight, 4);
+        assert_eq!(upgraded.authority, Pubkey::default());
+        assert!(ElectionAccountState::upgrade(ElectionAccountState::VERSION + 1, &data).is_err());
+    }
+
+    #[test]
+    fn legacy_candidate_list_matches_election_order() {
+        let (first, second) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let legacy_list = legacy::CandidateListState {
+            is_initialized: true,
+            candidates: HashMap::from([("Mario Rossi".to_string(), first), ("Anna Bianchi".to_string(), second)]),
+        };
+        let legacy_election = legacy::ElectionAccountState {
+            is_initialized: true,
+            name: "Elettorale1".to_string(),
+            start_date: String::new(),
+            end_date: String::new(),
+            votes: HashMap::from([(first, 0), (second, 0)]),
+            number_of_votes: 0,
+            is_active: false,
+        };
+
+        let list = CandidateListState::upgrade(0, &legacy_list.try_to_vec().unwrap()).unwrap();
+        let election = ElectionAccountState::upgrade(0, &legacy_election.try_to_vec().unwrap()).unwrap();
+
+        assert!(list.is_initialized);
+        assert_eq!(list.candidates.iter().map(|candidate| candidate.address).collect::<Vec<_>>(), election.candidates);
+        let index = list.index_of("Mario Rossi").unwrap();
+        assert_eq!(list.candidates[index].address, first);
+    }
+
+    #[test]
+    fn legacy_result_recovers_votes_from_percentages() {
+        let legacy = legacy::ResultState {
+            results: HashMap::from([
+                ("Mario Rossi".to_string(), (100.0 / 3.0_f32) * 2.0),
+                ("Anna Bianchi".to_string(), 100.0 / 3.0_f32),
+            ]),
+            number_of_votes: 3,
+        };
+
+        let upgraded = ResultState::upgrade(0, &legacy.try_to_vec().unwrap()).unwrap();
+
+        assert_eq!(upgraded.number_of_votes, 3);
+        assert_eq!(upgraded.total_weight, 3);
+        let votes: Vec<(&str, i64)> = upgraded.ranking.iter().map(|entry| (entry.name.as_str(), entry.votes)).collect();
+        assert_eq!(votes, vec![("Anna Bianchi", 1), ("Mario Rossi", 2)]);
+        assert!(!upgraded.is_final);
+    }
+
+    #[test]
+    fn legacy_voter_and_candidate_keep_their_fields() {
+        let voted = Pubkey::new_unique();
+        let voter = VoterAccountState::upgrade(0, &legacy::VoterAccountState {
+            election_card_number: "AA1234567".to_string(),
+            voted,
+        }.try_to_vec().unwrap()).unwrap();
+        assert_eq!(voter.election_card_number, "AA1234567");
+        assert_eq!(voter.voted, voted);
+        assert_eq!(voter.weight, 1);
+
+        let candidate = CandidateState::upgrade(0, &legacy::CandidateState {
+            is_initialized: true,
+            first_name: "Giuseppe".to_string(),
+            last_name: "Garibaldi".to_string(),
+        }.try_to_vec().unwrap()).unwrap();
+        assert!(candidate.is_initialized);
+        assert_eq!((candidate.first_name.as_str(), candidate.last_name.as_str()), ("Giuseppe", "Garibaldi"));
+    }
+
+    #[test]
+    fn accounts_without_a_legacy_layout_reject_version_zero() {
+        let data = DistrictState::default().try_to_vec().unwrap();
+        assert!(DistrictState::upgrade(0, &data).is_err());
+        let data = RollEntryState::default().try_to_vec().unwrap();
+        assert!(RollEntryState::upgrade(0, &data).is_err());
+    }
+}
@@ -1,10 +1,74 @@
-use std::collections::HashMap;
-
 use borsh::{BorshSerialize, BorshDeserialize};
 use solana_program::pubkey::Pubkey;
+use solana_program::program_error::ProgramError;
+use super::{account_header::{AccountState, read_legacy}, legacy};
+
+#[derive(BorshSerialize,BorshDeserialize,Clone,Default)]
+pub struct CandidateEntry{
+    pub name: String,
+    pub address: Pubkey,
+    //DATI USATI PER GLI SPAREGGI
+    pub birth_date: String,
+    pub registration_slot: u64,
+}
 
-#[derive(BorshSerialize,BorshDeserialize)]
+#[derive(BorshSerialize,BorshDeserialize,Default)]
 pub struct CandidateListState{
     pub is_initialized: bool,
-    pub candidates: HashMap<String, Pubkey>
+    //CANDIDATI IN ORDINE DI REGISTRAZIONE: LA POSIZIONE E' L'INDICE DEL CANDIDATO NELL'ELEZIONE
+    pub candidates: Vec<CandidateEntry>,
+}
+
+impl CandidateListState {
+    //DIMENSIONE DELLA LISTA VUOTA, L'ACCOUNT CRESCE CON REALLOC AD OGNI CANDIDATO
+    pub fn space() -> usize {
+        1 + 4
+    }
+
+    //INDICE DI UN CANDIDATO DAL NOME "NOME COGNOME"
+    pub fn index_of(&self, name: &str) -> Option<usize> {
+        self.candidates.iter().position(|candidate| candidate.name == name)
+    }
+}
+
+impl AccountState for CandidateListState {
+    const DISCRIMINATOR: [u8; 8] = [174, 225, 134, 141, 76, 159, 122, 200];
+    const VERSION: u8 = 1;
+
+    fn upgrade(version: u8, data: &[u8]) -> Result<Self, ProgramError> {
+        match version {
+            0 => Ok(Self::from(read_legacy::<legacy::CandidateListState>(data)?)),
+            _ => Err(ProgramError::InvalidAccountData),
+        }
+    }
+}
+
+//CONVERSIONE DAL LAYOUT SENZA INTESTAZIONE: CANDIDATI IN ORDINE DI INDIRIZZO, COME NELL'ELEZIONE CONVERTITA
+//Data di nascita e slot di registrazione non erano registrati e restano vuoti
+impl From<legacy::CandidateListState> for CandidateListState {
+    fn from(legacy: legacy::CandidateListState) -> Self {
+        let mut candidates: Vec<CandidateEntry> = legacy.candidates.into_iter()
+            .map(|(name, address)| CandidateEntry { name, address, ..Default::default() })
+            .collect();
+        candidates.sort_by_key(|candidate| candidate.address);
+
+        CandidateListState {
+            is_initialized: legacy.is_initialized,
+            candidates,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn space_matches_empty_list() {
+        let state = CandidateListState {
+            is_initialized: true,
+            ..Default::default()
+        };
+        assert_eq!(state.try_to_vec().unwrap().len(), CandidateListState::space());
+    }
 }
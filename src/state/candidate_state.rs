@@ -1,8 +1,55 @@
 use borsh::{BorshSerialize, BorshDeserialize};
+use solana_program::program_error::ProgramError;
+use super::{account_header::{AccountState, read_legacy}, legacy};
 
-#[derive(BorshSerialize, BorshDeserialize)]
+#[derive(BorshSerialize, BorshDeserialize, Default)]
 pub struct CandidateState {
     pub is_initialized: bool,
     pub first_name: String,
     pub last_name: String,
-}
\ No newline at end of file
+}
+
+impl CandidateState {
+    //DIMENSIONE BORSH: bool + DUE STRINGHE CON PREFISSO DI LUNGHEZZA u32
+    pub fn space(first_name: &str, last_name: &str) -> usize {
+        1 + (4 + first_name.len()) + (4 + last_name.len())
+    }
+}
+
+impl AccountState for CandidateState {
+    const DISCRIMINATOR: [u8; 8] = [70, 26, 232, 170, 112, 154, 236, 23];
+    const VERSION: u8 = 1;
+
+    fn upgrade(version: u8, data: &[u8]) -> Result<Self, ProgramError> {
+        match version {
+            0 => Ok(Self::from(read_legacy::<legacy::CandidateState>(data)?)),
+            _ => Err(ProgramError::InvalidAccountData),
+        }
+    }
+}
+
+//CONVERSIONE DAL LAYOUT SENZA INTESTAZIONE: GLI STESSI CAMPI
+impl From<legacy::CandidateState> for CandidateState {
+    fn from(legacy: legacy::CandidateState) -> Self {
+        CandidateState {
+            is_initialized: legacy.is_initialized,
+            first_name: legacy.first_name,
+            last_name: legacy.last_name,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn space_matches_serialized_layout() {
+        let state = CandidateState {
+            is_initialized: true,
+            first_name: "Giuseppe".to_string(),
+            last_name: "Garibaldi".to_string(),
+        };
+        assert_eq!(state.try_to_vec().unwrap().len(), CandidateState::space("Giuseppe", "Garibaldi"));
+    }
+}
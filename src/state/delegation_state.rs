@@ -0,0 +1,83 @@
+use borsh::{BorshSerialize, BorshDeserialize};
+use super::account_header::AccountState;
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, PartialEq, Debug, Default)]
+pub enum DelegationScope {
+    #[default]
+    Election,
+    Topic,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Default)]
+pub struct DelegationState {
+    pub is_initialized: bool,
+    pub scope: DelegationScope,
+    pub scope_name: String,
+    pub delegator: String,
+    pub delegate: String,
+    pub weight: u64,
+    pub is_active: bool,
+}
+
+impl DelegationState {
+    //DIMENSIONE BORSH: bool + AMBITO (1 BYTE) + TRE STRINGHE + PESO + bool
+    pub fn space(scope_name: &str, delegator: &str, delegate: &str) -> usize {
+        1 + 1 + (4 + scope_name.len()) + (4 + delegator.len()) + (4 + delegate.len()) + 8 + 1
+    }
+}
+
+impl AccountState for DelegationState {
+    const DISCRIMINATOR: [u8; 8] = [23, 95, 199, 213, 150, 28, 166, 187];
+    const VERSION: u8 = 1;
+}
+
+//NUMERO DI DELEGHE ATTIVE PER UN'ELEZIONE O PER UN TEMA
+//Lo scrutinio deve ricevere tutte le deleghe attive: chi scrutina non puo' ometterne alcuna.
+#[derive(BorshSerialize, BorshDeserialize, Default)]
+pub struct DelegationCounterState {
+    pub is_initialized: bool,
+    pub scope: DelegationScope,
+    pub scope_name: String,
+    pub active: u32,
+}
+
+impl DelegationCounterState {
+    pub fn space(scope_name: &str) -> usize {
+        1 + 1 + (4 + scope_name.len()) + 4
+    }
+}
+
+impl AccountState for DelegationCounterState {
+    const DISCRIMINATOR: [u8; 8] = [32, 152, 82, 51, 117, 35, 116, 48];
+    const VERSION: u8 = 1;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn space_matches_serialized_layout() {
+        let state = DelegationState {
+            is_initialized: true,
+            scope: DelegationScope::Topic,
+            scope_name: "Ambiente".to_string(),
+            delegator: "AA1234567".to_string(),
+            delegate: "BB7654321".to_string(),
+            weight: 2,
+            is_active: true,
+        };
+        assert_eq!(state.try_to_vec().unwrap().len(), DelegationState::space("Ambiente", "AA1234567", "BB7654321"));
+    }
+
+    #[test]
+    fn counter_space_matches_serialized_layout() {
+        let state = DelegationCounterState {
+            is_initialized: true,
+            scope: DelegationScope::Topic,
+            scope_name: "Ambiente".to_string(),
+            active: 3,
+        };
+        assert_eq!(state.try_to_vec().unwrap().len(), DelegationCounterState::space("Ambiente"));
+    }
+}
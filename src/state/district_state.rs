@@ -0,0 +1,40 @@
+use borsh::{BorshSerialize, BorshDeserialize};
+use super::account_header::AccountState;
+
+#[derive(BorshSerialize, BorshDeserialize, Default)]
+pub struct DistrictState {
+    pub is_initialized: bool,
+    pub name: String,
+    //CONTEGGI INDICIZZATI PER CANDIDATO, ESTESI AL PRIMO VOTO DI OGNI CANDIDATO
+    pub votes: Vec<i64>,
+    pub number_of_votes: i64,
+    pub headcount: Vec<i64>,
+    pub total_weight: i64,
+}
+
+impl DistrictState {
+    //DIMENSIONE DELLA CIRCOSCRIZIONE SENZA VOTI, L'ACCOUNT CRESCE CON REALLOC
+    pub fn space(name: &str) -> usize {
+        1 + (4 + name.len()) + 4 + 8 + 4 + 8
+    }
+}
+
+impl AccountState for DistrictState {
+    const DISCRIMINATOR: [u8; 8] = [151, 48, 117, 136, 13, 223, 158, 184];
+    const VERSION: u8 = 1;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn space_matches_empty_district() {
+        let state = DistrictState {
+            is_initialized: true,
+            name: "Nord".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(state.try_to_vec().unwrap().len(), DistrictState::space("Nord"));
+    }
+}
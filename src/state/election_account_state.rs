@@ -1,16 +1,168 @@
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 
 use borsh::{BorshSerialize, BorshDeserialize};
 use solana_program::pubkey::Pubkey;
+use solana_program::program_error::ProgramError;
+use super::{account_header::{AccountState, read_legacy}, legacy};
 
-#[derive(BorshSerialize, BorshDeserialize)]
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, PartialEq, Debug, Default)]
+pub enum VotingMode {
+    #[default]
+    Standard,
+    Weighted,
+    TokenWeighted,
+    Quadratic,
+}
+
+//NUMERO LEGALE DI VOTANTI PER LA VALIDITA' DELL'ELEZIONE
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, PartialEq, Debug, Default)]
+pub enum Quorum {
+    #[default]
+    None,
+    //NUMERO MINIMO DI VOTANTI
+    Absolute(u64),
+    //QUOTA MINIMA DEGLI ISCRITTI ALLE LISTE ELETTORALI, IN PUNTI BASE
+    RollShare(u32),
+}
+
+//MAGGIORANZA RICHIESTA PER ELEGGERE UN CANDIDATO
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, PartialEq, Debug, Default)]
+pub enum MajorityRule {
+    //MAGGIORANZA ASSOLUTA DEI VOTI (PIU' DEL 50%)
+    #[default]
+    Absolute,
+    //MAGGIORANZA RELATIVA: PIU' VOTI DI OGNI ALTRO CANDIDATO
+    Simple,
+    //ALMENO DUE TERZI DEI VOTI
+    TwoThirds,
+}
+
+//STATO DELLO SCRUTINIO
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, PartialEq, Debug, Default)]
+pub enum ElectionStatus {
+    #[default]
+    Open,
+    Tallied,
+    //RICONTEGGIO AUTORIZZATO DALL'AUTORITA', I RISULTATI POSSONO ESSERE RISCRITTI
+    Recounting,
+    //ARCHIVIATA DOPO IL PERIODO DI CONSERVAZIONE: RISULTATO IMMUTABILE, ACCOUNT DI VOTANTI E CANDIDATI CHIUDIBILI
+    Archived,
+}
+
+//REGOLA PER ORDINARE I CANDIDATI A PARI VOTI
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, PartialEq, Debug, Default)]
+pub enum TieBreakPolicy {
+    //NESSUNO SPAREGGIO: I CANDIDATI A PARI VOTI ACCEDONO TUTTI AL BALLOTTAGGIO
+    #[default]
+    Runoff,
+    OldestCandidate,
+    Lot,
+    EarlierRegistration,
+}
+
+//ESTRAZIONE CASUALE DEI GARANTI: ORDINE DEI CANDIDATI SULLA SCHEDA PRIMA DEL VOTO, SPAREGGIO DOPO LA CHIUSURA
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, PartialEq, Debug, Default)]
+pub enum RandomnessRound {
+    #[default]
+    TieBreak,
+    BallotOrder,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Default)]
 pub struct ElectionAccountState {
     pub is_initialized: bool,
     pub name: String,
     pub start_date: String,
     pub end_date: String,
-    pub votes: HashMap<Pubkey, i64>,
+    //CONTEGGI INDICIZZATI PER CANDIDATO, NELL'ORDINE DI candidates
+    pub votes: Vec<i64>,
     pub number_of_votes: i64,
     pub is_active: bool,
+    pub authority: Pubkey,
+    pub round: u8,
+    pub first_round: String,
+    pub number_of_districts: u32,
+    pub voting_mode: VotingMode,
+    pub headcount: Vec<i64>,
+    pub total_weight: i64,
+    pub roll_size: i64,
+    pub roll_weight: i64,
+    pub mint: Pubkey,
+    pub token_vault: Pubkey,
+    pub voice_credits: u64,
+    pub credits: Vec<i64>,
+    pub topic: String,
+    pub tie_break: TieBreakPolicy,
+    //GARANTI DEI SEMI CASUALI, DIVERSI DALL'AUTORITA'
+    pub trustees: Vec<Pubkey>,
+    pub randomness_commitments: BTreeMap<Pubkey, [u8; 32]>,
+    pub randomness_reveals: BTreeMap<Pubkey, [u8; 32]>,
+    pub random_seed: [u8; 32],
+    pub random_seed_ready: bool,
+    //SEME DELL'ORDINE DI SCHEDA: AL PRIMO IMPEGNO LA LISTA CANDIDATI SI CHIUDE
+    pub ballot_commitments: BTreeMap<Pubkey, [u8; 32]>,
+    pub ballot_reveals: BTreeMap<Pubkey, [u8; 32]>,
+    pub ballot_seed: [u8; 32],
+    //INDICI DEI CANDIDATI NELL'ORDINE IN CUI COMPAIONO SULLA SCHEDA (VUOTO = ORDINE DI REGISTRAZIONE)
+    pub ballot_order: Vec<u32>,
+    pub status: ElectionStatus,
+    pub recounts: u32,
+    pub quorum: Quorum,
+    pub majority_rule: MajorityRule,
+    //NASCONDE I CONTEGGI PER CANDIDATO NELLA VISTA PROVVISORIA FINO ALLA CHIUSURA
+    pub embargo: bool,
+    //CONTATORI DEI VOTI DISTRIBUITI SU PIU' ACCOUNT (0 = VOTI NELL'ACCOUNT CONTATORI)
+    pub number_of_shards: u8,
+    //CANDIDATI IN ORDINE DI REGISTRAZIONE: LA POSIZIONE E' L'INDICE DEL CANDIDATO
+    //IN votes, headcount, credits, NELLA LISTA CANDIDATI E NELL'ACCOUNT CONTATORI
+    pub candidates: Vec<Pubkey>,
 }
 
+impl ElectionAccountState {
+    //IL CANDIDATO OCCUPA LA PROSSIMA POSIZIONE IN OGNI CONTATORE
+    pub fn push_candidate(&mut self, candidate_address: Pubkey) {
+        self.candidates.push(candidate_address);
+        self.votes.push(0);
+        self.headcount.push(0);
+        self.credits.push(0);
+    }
+}
+
+impl AccountState for ElectionAccountState {
+    const DISCRIMINATOR: [u8; 8] = [175, 152, 224, 124, 196, 98, 235, 201];
+    const VERSION: u8 = 1;
+
+    fn upgrade(version: u8, data: &[u8]) -> Result<Self, ProgramError> {
+        match version {
+            0 => Ok(Self::from(read_legacy::<legacy::ElectionAccountState>(data)?)),
+            _ => Err(ProgramError::InvalidAccountData),
+        }
+    }
+}
+
+//CONVERSIONE DAL LAYOUT SENZA INTESTAZIONE
+//I candidati prendono l'ordine di indirizzo, lo stesso della lista candidati convertita.
+//Ogni voto valeva 1: votanti e peso coincidono con i voti. Il vecchio layout non aveva un'autorita'.
+impl From<legacy::ElectionAccountState> for ElectionAccountState {
+    fn from(legacy: legacy::ElectionAccountState) -> Self {
+        let mut votes: Vec<(Pubkey, i64)> = legacy.votes.into_iter().collect();
+        votes.sort();
+        let (candidates, votes): (Vec<Pubkey>, Vec<i64>) = votes.into_iter().unzip();
+
+        ElectionAccountState {
+            is_initialized: legacy.is_initialized,
+            name: legacy.name,
+            start_date: legacy.start_date,
+            end_date: legacy.end_date,
+            headcount: votes.clone(),
+            credits: vec![0; votes.len()],
+            votes,
+            number_of_votes: legacy.number_of_votes,
+            total_weight: legacy.number_of_votes,
+            is_active: legacy.is_active,
+            round: 1,
+            candidates,
+            ..Default::default()
+        }
+    }
+}
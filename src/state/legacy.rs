@@ -0,0 +1,47 @@
+use std::collections::HashMap;
+
+use borsh::{BorshSerialize, BorshDeserialize};
+use solana_program::pubkey::Pubkey;
+
+//LAYOUT DEGLI ACCOUNT CREATI PRIMA DELL'INTESTAZIONE (VERSIONE 0), LETTI SOLO PER LA MIGRAZIONE
+//Le mappe sono serializzate da Borsh in ordine di chiave, quindi le conversioni sono deterministiche.
+//Solo questi tipi esistevano prima dell'intestazione: gli altri account non hanno una versione 0.
+
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct ElectionAccountState {
+    pub is_initialized: bool,
+    pub name: String,
+    pub start_date: String,
+    pub end_date: String,
+    //VOTI PER INDIRIZZO DEL CANDIDATO, OGNI CANDIDATO INSERITO A 0 ALLA REGISTRAZIONE
+    pub votes: HashMap<Pubkey, i64>,
+    pub number_of_votes: i64,
+    pub is_active: bool,
+}
+
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct CandidateListState {
+    pub is_initialized: bool,
+    //INDIRIZZO PER NOME "NOME COGNOME"
+    pub candidates: HashMap<String, Pubkey>,
+}
+
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct ResultState {
+    //PERCENTUALE PER NOME, CALCOLATA IN f32 COME (100 / VOTI TOTALI) * VOTI
+    pub results: HashMap<String, f32>,
+    pub number_of_votes: i64,
+}
+
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct CandidateState {
+    pub is_initialized: bool,
+    pub first_name: String,
+    pub last_name: String,
+}
+
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct VoterAccountState {
+    pub election_card_number: String,
+    pub voted: Pubkey,
+}
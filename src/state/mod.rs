@@ -3,3 +3,12 @@ pub mod election_account_state;
 pub mod candidate_list_state;
 pub mod voter_account_state;
 pub mod result_state;
+pub mod district_state;
+pub mod roll_entry_state;
+pub mod delegation_state;
+pub mod provisional_results_state;
+pub mod shard_state;
+pub mod vote_counters_state;
+pub mod account_header;
+pub mod relay_nonce_state;
+pub mod legacy;
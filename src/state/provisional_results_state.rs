@@ -0,0 +1,12 @@
+use borsh::{BorshSerialize, BorshDeserialize};
+use solana_program::pubkey::Pubkey;
+
+//VISTA PROVVISORIA RESTITUITA DA ShowProvisionalResults TRAMITE RETURN DATA
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct ProvisionalResultsState {
+    pub number_of_votes: i64,
+    pub total_weight: i64,
+    pub roll_size: i64,
+    //NESSUN CONTEGGIO PER CANDIDATO FINCHE' L'EMBARGO E' IN VIGORE
+    pub votes: Option<Vec<(Pubkey, i64)>>,
+}
@@ -0,0 +1,36 @@
+use borsh::{BorshSerialize, BorshDeserialize};
+use solana_program::pubkey::Pubkey;
+use super::account_header::AccountState;
+
+//NONCE DELLE SCHEDE FIRMATE DA UN VOTANTE E INOLTRATE DA UN RELAYER
+#[derive(BorshSerialize, BorshDeserialize, Default)]
+pub struct RelayNonceState {
+    pub voter: Pubkey,
+    //NONCE CHE LA PROSSIMA SCHEDA FIRMATA DEVE CONTENERE
+    pub nonce: u64,
+}
+
+impl RelayNonceState {
+    pub fn space() -> usize {
+        32 + 8
+    }
+}
+
+impl AccountState for RelayNonceState {
+    const DISCRIMINATOR: [u8; 8] = [132, 13, 71, 99, 233, 93, 197, 33];
+    const VERSION: u8 = 1;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn space_matches_serialized_layout() {
+        let state = RelayNonceState {
+            voter: Pubkey::new_unique(),
+            nonce: 7,
+        };
+        assert_eq!(state.try_to_vec().unwrap().len(), RelayNonceState::space());
+    }
+}
@@ -1,9 +1,115 @@
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 
 use borsh::{BorshSerialize, BorshDeserialize};
+use solana_program::pubkey::Pubkey;
 
-#[derive(BorshSerialize, BorshDeserialize)]
+use super::election_account_state::{TieBreakPolicy, MajorityRule};
+use solana_program::program_error::ProgramError;
+use super::{account_header::{AccountState, read_legacy}, legacy};
+
+//ESITO DELLO SCRUTINIO SECONDO QUORUM E MAGGIORANZA DELL'ELEZIONE
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, PartialEq, Debug, Default)]
+pub enum ElectionOutcome {
+    #[default]
+    Pending,
+    Valid,
+    QuorumNotReached,
+    NoMajority,
+}
+
+//POSIZIONE DI UN CANDIDATO NELLA CLASSIFICA UFFICIALE
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug)]
+pub struct RankedEntry {
+    //A PARI VOTI STESSA POSIZIONE (1, 1, 3)
+    pub rank: u32,
+    //INDICE DEL CANDIDATO NELL'ELEZIONE
+    pub index: u32,
+    pub candidate: Pubkey,
+    pub name: String,
+    pub votes: i64,
+    //QUOTA IN PUNTI BASE (10000 = 100%), ARROTONDATA PER DIFETTO
+    pub share: u32,
+    //PARI VOTI CON ALMENO UN ALTRO CANDIDATO
+    pub tied: bool,
+}
+
+//SPAREGGIO APPLICATO A UN GRUPPO DI CANDIDATI A PARI VOTI
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug)]
+pub struct TieBreakRecord {
+    pub rule: TieBreakPolicy,
+    //POSIZIONE CONTESA
+    pub rank: u32,
+    //CANDIDATI NELL'ORDINE STABILITO DALLO SPAREGGIO
+    pub candidates: Vec<Pubkey>,
+    //VALORE CONFRONTATO PER OGNI CANDIDATO (DATA DI NASCITA, SLOT O ESTRAZIONE)
+    pub inputs: Vec<String>,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Default)]
 pub struct ResultState {
-    pub results: HashMap<String, f32>,
-    pub number_of_votes: i64
-}
\ No newline at end of file
+    //CLASSIFICA ORDINATA PER VOTI DECRESCENTI, A PARITA' PER NOME
+    pub ranking: Vec<RankedEntry>,
+    pub number_of_votes: i64,
+    pub runoff_required: bool,
+    pub district_turnout: BTreeMap<String, i64>,
+    pub total_weight: i64,
+    pub headcount: BTreeMap<String, i64>,
+    pub headcount_results: BTreeMap<String, u32>,
+    pub weighted_majority: String,
+    pub headcount_majority: String,
+    pub tie_breaks: Vec<TieBreakRecord>,
+    pub lot_seed: [u8; 32],
+    //SCRUTINIO DEFINITIVO: DOPO QUESTO PUNTO IL RISULTATO NON CAMBIA SENZA RICONTEGGIO
+    pub is_final: bool,
+    pub tallied_slot: u64,
+    pub tallied_by: Pubkey,
+    pub recount: u32,
+    pub outcome: ElectionOutcome,
+    //VOTANTI NECESSARI PER IL QUORUM (0 SE NON PREVISTO)
+    pub quorum_required: u64,
+    pub majority_rule: MajorityRule,
+    pub winner: String,
+    //SCRUTINIO A PAGINE: CANDIDATI GIA' CONTATI E SCRUTINIO NON ANCORA CONCLUSO
+    pub tally_cursor: u32,
+    pub tally_in_progress: bool,
+}
+
+impl AccountState for ResultState {
+    const DISCRIMINATOR: [u8; 8] = [186, 126, 46, 221, 79, 201, 213, 52];
+    const VERSION: u8 = 1;
+
+    fn upgrade(version: u8, data: &[u8]) -> Result<Self, ProgramError> {
+        match version {
+            0 => Ok(Self::from(read_legacy::<legacy::ResultState>(data)?)),
+            _ => Err(ProgramError::InvalidAccountData),
+        }
+    }
+}
+
+//CONVERSIONE DAL LAYOUT SENZA INTESTAZIONE
+//Il vecchio risultato aveva solo le percentuali f32: i voti si ricavano da percentuale e totale,
+//esatti finche' l'errore di f32 resta sotto mezzo voto. Indice, indirizzo e posizione dei candidati
+//non erano registrati: la migrazione li completa dalla lista candidati e rifa' la classifica.
+impl From<legacy::ResultState> for ResultState {
+    fn from(legacy: legacy::ResultState) -> Self {
+        let mut ranking: Vec<RankedEntry> = legacy.results.into_iter()
+            .map(|(name, percentage)| RankedEntry {
+                rank: 0,
+                index: 0,
+                candidate: Pubkey::default(),
+                name,
+                votes: (f64::from(percentage) * legacy.number_of_votes as f64 / 100.0).round() as i64,
+                share: 0,
+                tied: false,
+            })
+            .collect();
+        ranking.sort_by(|a, b| a.name.cmp(&b.name));
+
+        ResultState {
+            ranking,
+            number_of_votes: legacy.number_of_votes,
+            total_weight: legacy.number_of_votes,
+            ..Default::default()
+        }
+    }
+}
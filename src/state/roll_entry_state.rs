@@ -0,0 +1,39 @@
+use borsh::{BorshSerialize, BorshDeserialize};
+use solana_program::pubkey::Pubkey;
+use super::account_header::AccountState;
+
+#[derive(BorshSerialize, BorshDeserialize, Default)]
+pub struct RollEntryState {
+    pub is_initialized: bool,
+    pub electoral_card_number: String,
+    pub weight: u64,
+    //CHIAVE DEL TITOLARE DELLA TESSERA, FIRMA DELEGHE E SCHEDE INOLTRATE
+    pub voter: Pubkey,
+}
+
+impl RollEntryState {
+    pub fn space(electoral_card_number: &str) -> usize {
+        1 + (4 + electoral_card_number.len()) + 8 + 32
+    }
+}
+
+impl AccountState for RollEntryState {
+    const DISCRIMINATOR: [u8; 8] = [218, 223, 202, 131, 12, 116, 39, 209];
+    const VERSION: u8 = 1;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn space_matches_serialized_layout() {
+        let state = RollEntryState {
+            is_initialized: true,
+            electoral_card_number: "AA1234567".to_string(),
+            weight: 3,
+            voter: Pubkey::new_unique(),
+        };
+        assert_eq!(state.try_to_vec().unwrap().len(), RollEntryState::space("AA1234567"));
+    }
+}
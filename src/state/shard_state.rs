@@ -0,0 +1,9 @@
+//CONTATORE DISTRIBUITO A LAYOUT FISSO, LETTO E SCRITTO SENZA DESERIALIZZARE (ZERO-COPY)
+//Stesso layout dell'account contatori: VoteCountersHeader seguito da un CandidateCounter per
+//ogni posizione di candidato, con un proprio discriminatore. L'indice del contatore e' nel suo PDA.
+pub struct ShardState;
+
+impl ShardState {
+    pub const DISCRIMINATOR: [u8; 8] = [165, 239, 226, 25, 71, 201, 237, 188];
+    pub const VERSION: u8 = 1;
+}
@@ -0,0 +1,44 @@
+use bytemuck::{Pod, Zeroable};
+
+//ACCOUNT CONTATORI A LAYOUT FISSO, LETTO E SCRITTO SENZA DESERIALIZZARE (ZERO-COPY)
+//Layout: VoteCountersHeader seguito da un CandidateCounter per ogni posizione di candidato.
+//L'intestazione inizia con discriminatore e versione come gli altri account, con 7 byte di
+//riempimento per tenere allineati a 8 byte i contatori.
+
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+pub struct VoteCountersHeader {
+    pub discriminator: [u8; 8],
+    pub version: u8,
+    pub padding: [u8; 7],
+    pub number_of_votes: i64,
+    pub total_weight: i64,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+pub struct CandidateCounter {
+    pub votes: i64,
+    pub headcount: i64,
+    //CREDITI SPESI PER IL CANDIDATO NEL VOTO QUADRATICO
+    pub credits: i64,
+}
+
+impl VoteCountersHeader {
+    pub const LEN: usize = std::mem::size_of::<VoteCountersHeader>();
+    pub const DISCRIMINATOR: [u8; 8] = [119, 233, 229, 124, 7, 103, 9, 194];
+    pub const VERSION: u8 = 1;
+
+    pub fn space(number_of_candidates: usize) -> usize {
+        VoteCountersHeader::LEN + number_of_candidates * CandidateCounter::LEN
+    }
+}
+
+impl CandidateCounter {
+    pub const LEN: usize = std::mem::size_of::<CandidateCounter>();
+
+    //OFFSET DEL CONTATORE DI UN CANDIDATO NELL'ACCOUNT
+    pub fn offset(slot: usize) -> usize {
+        VoteCountersHeader::LEN + slot * CandidateCounter::LEN
+    }
+}
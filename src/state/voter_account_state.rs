@@ -1,10 +1,104 @@
 
+use std::collections::BTreeMap;
+
 use borsh::{BorshSerialize, BorshDeserialize};
 use solana_program::pubkey::Pubkey;
+use solana_program::program_error::ProgramError;
+use super::{account_header::{AccountState, read_legacy}, legacy};
 
-#[derive(BorshSerialize, BorshDeserialize)]
+#[derive(BorshSerialize, BorshDeserialize, Default)]
 pub struct VoterAccountState {
    pub election_card_number: String,
-   pub voted: Pubkey
+   pub voted: Pubkey,
+   //IMPEGNO DELLA SCHEDA SIGILLATA SOTTO EMBARGO: voted RESTA VUOTO FINO ALLA RIVELAZIONE
+   pub sealed_ballot: [u8; 32],
+   pub district: String,
+   pub weight: u64,
+   pub token_account: Pubkey,
+   pub locked_amount: u64,
+   pub remaining_credits: u64,
+   //VOTI QUADRATICI PER INDICE DEL CANDIDATO
+   pub allocations: BTreeMap<u32, u64>
+}
+
+impl VoterAccountState {
+    //DIMENSIONE BORSH CON SPAZIO PER allocations VOCI DEL VOTO QUADRATICO
+    pub fn space(election_card_number: &str, district: &str, allocations: usize) -> usize {
+        (4 + election_card_number.len())
+            + 32
+            + 32
+            + (4 + district.len())
+            + 8
+            + 32
+            + 8
+            + 8
+            + 4 + allocations * (4 + 8)
+    }
+}
+
+impl AccountState for VoterAccountState {
+    const DISCRIMINATOR: [u8; 8] = [133, 234, 215, 229, 16, 164, 158, 90];
+    const VERSION: u8 = 1;
+
+    fn upgrade(version: u8, data: &[u8]) -> Result<Self, ProgramError> {
+        match version {
+            0 => Ok(Self::from(read_legacy::<legacy::VoterAccountState>(data)?)),
+            _ => Err(ProgramError::InvalidAccountData),
+        }
+    }
 }
 
+//CONVERSIONE DAL LAYOUT SENZA INTESTAZIONE: VOTO PER TESTA, SENZA CIRCOSCRIZIONE
+impl From<legacy::VoterAccountState> for VoterAccountState {
+    fn from(legacy: legacy::VoterAccountState) -> Self {
+        VoterAccountState {
+            election_card_number: legacy.election_card_number,
+            voted: legacy.voted,
+            weight: 1,
+            ..Default::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn space_matches_serialized_layout() {
+        let mut state = VoterAccountState {
+            election_card_number: "AA1234567".to_string(),
+            voted: Pubkey::new_unique(),
+            sealed_ballot: [7; 32],
+            district: "Nord".to_string(),
+            weight: 1,
+            token_account: Pubkey::default(),
+            locked_amount: 0,
+            remaining_credits: 100,
+            allocations: BTreeMap::new(),
+        };
+        assert_eq!(state.try_to_vec().unwrap().len(), VoterAccountState::space("AA1234567", "Nord", 0));
+
+        for index in 0..3 {
+            state.allocations.insert(index, 1);
+        }
+        assert_eq!(state.try_to_vec().unwrap().len(), VoterAccountState::space("AA1234567", "Nord", 3));
+    }
+
+    #[test]
+    fn token_voter_fits_allocation() {
+        //IL VOTANTE CON TOKEN NON HA TESSERA NE' CIRCOSCRIZIONE
+        let state = VoterAccountState {
+            election_card_number: String::new(),
+            voted: Pubkey::new_unique(),
+            sealed_ballot: [0; 32],
+            district: String::new(),
+            weight: u64::MAX,
+            token_account: Pubkey::new_unique(),
+            locked_amount: u64::MAX,
+            remaining_credits: 0,
+            allocations: BTreeMap::new(),
+        };
+        assert!(state.try_to_vec().unwrap().len() <= VoterAccountState::space("", "", 0));
+    }
+}
@@ -0,0 +1,91 @@
+use solana_program::{
+    account_info::AccountInfo,
+    entrypoint::{ProgramResult, MAX_PERMITTED_DATA_INCREASE},
+    msg,
+    program::invoke,
+    program_error::ProgramError,
+    system_instruction::{self, MAX_PERMITTED_DATA_LENGTH},
+    system_program,
+    sysvar::{rent::Rent, Sysvar},
+};
+
+use crate::{state::account_header::AccountState, utilities::account_state_utilities::pack_account};
+
+//INGRANDISCE L'ACCOUNT A new_len BYTE, PAGANDO IL RENT MANCANTE CON payer
+//I nuovi byte sono azzerati; l'account non viene mai ridotto.
+pub fn realloc_with_rent<'a>(
+    account: &AccountInfo<'a>,
+    new_len: usize,
+    payer: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>
+) -> ProgramResult {
+    if new_len <= account.data_len() {
+        return Ok(())
+    }
+    check_realloc(account, new_len)?;
+    if !payer.is_signer {
+        return Err(ProgramError::MissingRequiredSignature)
+    }
+
+    //PAGA IL RENT PER LA NUOVA DIMENSIONE
+    let missing_lamports = Rent::get()?.minimum_balance(new_len).saturating_sub(account.lamports());
+    if missing_lamports > 0 {
+        invoke(
+            &system_instruction::transfer(payer.key, account.key, missing_lamports),
+            &[payer.clone(), account.clone(), system_program.clone()]
+        )?;
+    }
+    account.realloc(new_len, true)
+}
+
+//VERIFICA CHE L'ACCOUNT POSSA CRESCERE A new_len BYTE IN QUESTA ISTRUZIONE, SENZA MODIFICARLO
+pub fn check_realloc(
+    account: &AccountInfo,
+    new_len: usize
+) -> ProgramResult {
+    if new_len as u64 > MAX_PERMITTED_DATA_LENGTH {
+        msg!("Account {} would need {} bytes, over the 10 MB limit", account.key, new_len);
+        return Err(ProgramError::InvalidRealloc)
+    }
+    if new_len.saturating_sub(account.data_len()) > MAX_PERMITTED_DATA_INCREASE {
+        msg!("Account {} cannot grow by more than {} bytes in one instruction", account.key, MAX_PERMITTED_DATA_INCREASE);
+        return Err(ProgramError::InvalidRealloc)
+    }
+
+    Ok(())
+}
+
+//SERIALIZZA LO STATO CON LA SUA INTESTAZIONE, INGRANDENDO L'ACCOUNT CON REALLOC SE NON C'E' SPAZIO
+pub fn save_with_realloc<'a, T: AccountState>(
+    state: &T,
+    account: &AccountInfo<'a>,
+    payer: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>
+) -> ProgramResult {
+    let data = pack_account(state)?;
+
+    realloc_with_rent(account, data.len(), payer, system_program)?;
+    account.data.borrow_mut()[..data.len()].copy_from_slice(&data);
+
+    Ok(())
+}
+
+//CHIUDE L'ACCOUNT: TUTTI I LAMPORTS A recipient, DATI AZZERATI E PROPRIETA' AL SYSTEM PROGRAM
+pub fn close_account(
+    account: &AccountInfo,
+    recipient: &AccountInfo
+) -> ProgramResult {
+    if account.key == recipient.key {
+        return Err(ProgramError::InvalidArgument)
+    }
+
+    let lamports = account.lamports();
+    let recipient_lamports = recipient.lamports().checked_add(lamports).ok_or(ProgramError::InvalidArgument)?;
+    **recipient.try_borrow_mut_lamports()? = recipient_lamports;
+    **account.try_borrow_mut_lamports()? = 0;
+
+    account.realloc(0, false)?;
+    account.assign(&system_program::ID);
+
+    Ok(())
+}
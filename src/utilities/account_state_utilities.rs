@@ -0,0 +1,92 @@
+use solana_program::{
+    account_info::AccountInfo,
+    borsh0_10::try_from_slice_unchecked,
+    entrypoint::ProgramResult,
+    msg,
+    program_error::ProgramError,
+};
+
+use crate::state::account_header::{AccountState, ACCOUNT_HEADER_LEN, DISCRIMINATOR_LEN};
+
+//LEGGE LO STATO DI UN ACCOUNT DOPO AVER VERIFICATO DISCRIMINATORE E VERSIONE
+pub fn load_account<T: AccountState>(
+    account: &AccountInfo
+) -> Result<T, ProgramError> {
+    let data = account.data.borrow();
+
+    if data.len() < ACCOUNT_HEADER_LEN || data[..DISCRIMINATOR_LEN] != T::DISCRIMINATOR {
+        msg!("Account {} is not a {}", account.key, std::any::type_name::<T>());
+        return Err(ProgramError::InvalidAccountData)
+    }
+    if data[DISCRIMINATOR_LEN] != T::VERSION {
+        msg!("Account {} has version {}, expected {}", account.key, data[DISCRIMINATOR_LEN], T::VERSION);
+        return Err(ProgramError::InvalidAccountData)
+    }
+
+    try_from_slice_unchecked::<T>(&data[ACCOUNT_HEADER_LEN..]).map_err(|_| ProgramError::InvalidAccountData)
+}
+
+//VERIFICA SE L'ACCOUNT INIZIA CON IL DISCRIMINATORE DEL TIPO T
+pub fn is_account_of<T: AccountState>(
+    account: &AccountInfo
+) -> bool {
+    let data = account.data.borrow();
+    data.len() >= ACCOUNT_HEADER_LEN && data[..DISCRIMINATOR_LEN] == T::DISCRIMINATOR
+}
+
+//VERSIONE DEL LAYOUT DI UN ACCOUNT: 0 SE CREATO PRIMA DELL'INTESTAZIONE
+//Va usata solo su account il cui tipo e' garantito dall'indirizzo (seed del PDA verificati)
+pub fn account_version<T: AccountState>(
+    account: &AccountInfo
+) -> u8 {
+    if is_account_of::<T>(account) {
+        account.data.borrow()[DISCRIMINATOR_LEN]
+    } else {
+        0
+    }
+}
+
+//LEGGE UN ACCOUNT DI QUALSIASI VERSIONE NON SUCCESSIVA A QUELLA CORRENTE, PORTANDOLO AL LAYOUT CORRENTE
+pub fn load_any_version<T: AccountState>(
+    account: &AccountInfo
+) -> Result<T, ProgramError> {
+    let version = account_version::<T>(account);
+
+    if version == T::VERSION {
+        return load_account::<T>(account)
+    }
+    if version > T::VERSION {
+        msg!("Account {} has version {}, newer than {}", account.key, version, T::VERSION);
+        return Err(ProgramError::InvalidAccountData)
+    }
+
+    let data = account.data.borrow();
+    let body = if version == 0 { &data[..] } else { &data[ACCOUNT_HEADER_LEN..] };
+    T::upgrade(version, body)
+}
+
+//INTESTAZIONE SEGUITA DALLO STATO SERIALIZZATO
+pub fn pack_account<T: AccountState>(
+    state: &T
+) -> Result<Vec<u8>, ProgramError> {
+    let mut data = T::header().to_vec();
+    state.serialize(&mut data)?;
+    Ok(data)
+}
+
+//SALVA LO STATO CON LA SUA INTESTAZIONE IN UN ACCOUNT GIA' ABBASTANZA GRANDE
+pub fn save_account<T: AccountState>(
+    state: &T,
+    account: &AccountInfo
+) -> ProgramResult {
+    let data = pack_account(state)?;
+    let mut account_data = account.data.borrow_mut();
+
+    if account_data.len() < data.len() {
+        msg!("Account {} needs {} bytes, has {}", account.key, data.len(), account_data.len());
+        return Err(ProgramError::AccountDataTooSmall)
+    }
+    account_data[..data.len()].copy_from_slice(&data);
+
+    Ok(())
+}
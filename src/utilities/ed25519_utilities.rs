@@ -0,0 +1,182 @@
+use borsh::{BorshSerialize, BorshDeserialize};
+use solana_program::{
+    account_info::AccountInfo,
+    ed25519_program,
+    entrypoint::ProgramResult,
+    msg,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    sysvar::instructions::{load_current_index_checked, load_instruction_at_checked},
+};
+
+//LAYOUT DEI DATI DELL'ISTRUZIONE DEL PRECOMPILE ED25519:
+//NUMERO DI FIRME (1 BYTE), PADDING (1 BYTE), POI 7 OFFSET U16 PER OGNI FIRMA
+const SIGNATURE_OFFSETS_START: usize = 2;
+const SIGNATURE_OFFSETS_LEN: usize = 14;
+const PUBKEY_LEN: usize = 32;
+const SIGNATURE_LEN: usize = 64;
+//INDICE CHE INDICA I DATI CONTENUTI NELLA STESSA ISTRUZIONE DEL PRECOMPILE
+const CURRENT_INSTRUCTION: u16 = u16::MAX;
+
+//PREFISSO DI DOMINIO: UNA FIRMA SU UNA SCHEDA NON VALE PER ALTRI MESSAGGI DELLA STESSA CHIAVE
+pub const BALLOT_DOMAIN: &[u8] = b"chain-democracy ballot v1\0";
+
+//SCHEDA CHE IL VOTANTE FIRMA FUORI CATENA, SERIALIZZATA CON BORSH DOPO IL PREFISSO DI DOMINIO
+//Le stringhe hanno il prefisso di lunghezza, quindi due schede diverse non danno mai gli stessi byte
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug)]
+pub struct BallotMessage {
+    pub program_id: Pubkey,
+    pub election_name: String,
+    pub electoral_card_number: String,
+    pub candidate_index: u32,
+    pub district_name: Option<String>,
+    pub nonce: u64,
+}
+
+pub fn ballot_message(
+    ballot: &BallotMessage
+) -> Result<Vec<u8>, ProgramError> {
+    let mut message = BALLOT_DOMAIN.to_vec();
+    ballot.serialize(&mut message)?;
+    Ok(message)
+}
+
+//VERIFICA CHE L'ISTRUZIONE PRECEDENTE DELLA TRANSAZIONE SIA IL PRECOMPILE ED25519
+//E CHE ABBIA VERIFICATO LA FIRMA DI signer SU message
+pub fn verify_ed25519_signature(
+    instructions_sysvar: &AccountInfo,
+    signer: &Pubkey,
+    message: &[u8]
+) -> ProgramResult {
+    let current_index = load_current_index_checked(instructions_sysvar)?;
+    if current_index == 0 {
+        msg!("Missing ed25519 signature instruction");
+        return Err(ProgramError::MissingRequiredSignature)
+    }
+
+    let instruction = load_instruction_at_checked(current_index as usize - 1, instructions_sysvar)?;
+    if instruction.program_id != ed25519_program::ID {
+        msg!("Previous instruction is not an ed25519 signature check");
+        return Err(ProgramError::MissingRequiredSignature)
+    }
+
+    let (signed_key, signed_message) = ed25519_signed_data(&instruction.data)?;
+    if signed_key != signer.as_ref() || signed_message != message {
+        msg!("Ballot not signed by {}", signer);
+        return Err(ProgramError::MissingRequiredSignature)
+    }
+
+    Ok(())
+}
+
+//ESTRAE CHIAVE PUBBLICA E MESSAGGIO DALL'UNICA FIRMA DELL'ISTRUZIONE ED25519
+//I dati devono stare nella stessa istruzione: un offset verso un'altra istruzione
+//farebbe verificare al precompile byte diversi da quelli letti qui.
+fn ed25519_signed_data(
+    data: &[u8]
+) -> Result<(&[u8], &[u8]), ProgramError> {
+    if data.len() < SIGNATURE_OFFSETS_START + SIGNATURE_OFFSETS_LEN || data[0] != 1 {
+        msg!("Expected exactly one ed25519 signature");
+        return Err(ProgramError::InvalidInstructionData)
+    }
+
+    let offsets: Vec<u16> = data[SIGNATURE_OFFSETS_START..SIGNATURE_OFFSETS_START + SIGNATURE_OFFSETS_LEN]
+        .chunks_exact(2)
+        .map(|chunk| u16::from_le_bytes([chunk[0], chunk[1]]))
+        .collect();
+    let (signature_offset, signature_instruction) = (offsets[0] as usize, offsets[1]);
+    let (pubkey_offset, pubkey_instruction) = (offsets[2] as usize, offsets[3]);
+    let (message_offset, message_len, message_instruction) = (offsets[4] as usize, offsets[5] as usize, offsets[6]);
+
+    if signature_instruction != CURRENT_INSTRUCTION || pubkey_instruction != CURRENT_INSTRUCTION || message_instruction != CURRENT_INSTRUCTION {
+        msg!("Ed25519 signature data must be inline");
+        return Err(ProgramError::InvalidInstructionData)
+    }
+    if data.len() < signature_offset + SIGNATURE_LEN {
+        return Err(ProgramError::InvalidInstructionData)
+    }
+
+    let signed_key = data.get(pubkey_offset..pubkey_offset + PUBKEY_LEN).ok_or(ProgramError::InvalidInstructionData)?;
+    let signed_message = data.get(message_offset..message_offset + message_len).ok_or(ProgramError::InvalidInstructionData)?;
+
+    Ok((signed_key, signed_message))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    //DATI DEL PRECOMPILE CON UNA FIRMA: OFFSET, CHIAVE, FIRMA E MESSAGGIO NELLA STESSA ISTRUZIONE
+    fn precompile_data(count: u8, instruction_indexes: [u16; 3], key: &[u8; 32], message: &[u8]) -> Vec<u8> {
+        let pubkey_offset = SIGNATURE_OFFSETS_START + SIGNATURE_OFFSETS_LEN;
+        let signature_offset = pubkey_offset + PUBKEY_LEN;
+        let message_offset = signature_offset + SIGNATURE_LEN;
+
+        let mut data = vec![count, 0];
+        for offset in [
+            signature_offset as u16, instruction_indexes[0],
+            pubkey_offset as u16, instruction_indexes[1],
+            message_offset as u16, message.len() as u16, instruction_indexes[2],
+        ] {
+            data.extend_from_slice(&offset.to_le_bytes());
+        }
+        data.extend_from_slice(key);
+        data.extend_from_slice(&[7; SIGNATURE_LEN]);
+        data.extend_from_slice(message);
+        data
+    }
+
+    const INLINE: [u16; 3] = [CURRENT_INSTRUCTION; 3];
+
+    #[test]
+    fn reads_key_and_message_at_their_offsets() {
+        let key = [3; 32];
+        let data = precompile_data(1, INLINE, &key, b"scheda");
+
+        let (signed_key, signed_message) = ed25519_signed_data(&data).unwrap();
+        assert_eq!(signed_key, key);
+        assert_eq!(signed_message, b"scheda");
+    }
+
+    #[test]
+    fn rejects_data_in_other_instructions() {
+        let key = [3; 32];
+        for position in 0..3 {
+            let mut instruction_indexes = INLINE;
+            instruction_indexes[position] = 0;
+            assert!(ed25519_signed_data(&precompile_data(1, instruction_indexes, &key, b"scheda")).is_err());
+        }
+    }
+
+    #[test]
+    fn requires_exactly_one_signature() {
+        let key = [3; 32];
+        assert!(ed25519_signed_data(&precompile_data(0, INLINE, &key, b"scheda")).is_err());
+        assert!(ed25519_signed_data(&precompile_data(2, INLINE, &key, b"scheda")).is_err());
+    }
+
+    #[test]
+    fn rejects_message_past_the_end() {
+        let key = [3; 32];
+        let mut data = precompile_data(1, INLINE, &key, b"scheda");
+        data.truncate(data.len() - 1);
+        assert!(ed25519_signed_data(&data).is_err());
+    }
+
+    #[test]
+    fn ballot_fields_do_not_run_together() {
+        let ballot = |election_name: &str, electoral_card_number: &str| BallotMessage {
+            program_id: Pubkey::default(),
+            election_name: election_name.to_string(),
+            electoral_card_number: electoral_card_number.to_string(),
+            candidate_index: 0,
+            district_name: None,
+            nonce: 0,
+        };
+
+        let message = ballot_message(&ballot("Elettorale1", "AA1")).unwrap();
+        assert!(message.starts_with(BALLOT_DOMAIN));
+        assert_ne!(message, ballot_message(&ballot("Elettorale", "1AA1")).unwrap());
+        assert_eq!(BallotMessage::try_from_slice(&message[BALLOT_DOMAIN.len()..]).unwrap(), ballot("Elettorale1", "AA1"));
+    }
+}
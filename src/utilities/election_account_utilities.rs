@@ -1,3 +1,6 @@
+use chrono::NaiveDateTime;
+use solana_program::{clock::Clock, program_error::ProgramError, sysvar::Sysvar};
+
 // use chrono::{NaiveDateTime, Utc, Duration};
 // use solana_program::{entrypoint::ProgramResult, program_error::ProgramError};
 
@@ -59,4 +62,38 @@
 //     }else{
 //         return Err(ProgramError::InvalidInstructionData);
 //     }
-// }
\ No newline at end of file
+// }
+
+//VERIFICA SE L'ELEZIONE E' TERMINATA CONFRONTANDO LA DATA DI FINE CON IL CLOCK
+pub fn is_election_closed(
+    end_date: &str
+) -> Result<bool, ProgramError> {
+
+    let parsed_end_date = NaiveDateTime::parse_from_str(end_date, "%Y-%m-%d %H:%M:%S").map_err(|_| ProgramError::InvalidAccountData)?;
+    let now = Clock::get()?.unix_timestamp;
+
+    Ok(now >= parsed_end_date.timestamp())
+}
+
+//VERIFICA SE DALLA FINE DELL'ELEZIONE SONO TRASCORSI ALMENO retention_seconds SECONDI
+pub fn is_retention_elapsed(
+    end_date: &str,
+    retention_seconds: i64
+) -> Result<bool, ProgramError> {
+
+    let parsed_end_date = NaiveDateTime::parse_from_str(end_date, "%Y-%m-%d %H:%M:%S").map_err(|_| ProgramError::InvalidAccountData)?;
+    let now = Clock::get()?.unix_timestamp;
+
+    Ok(now >= parsed_end_date.timestamp().saturating_add(retention_seconds))
+}
+
+//VERIFICA SE L'ELEZIONE E' INIZIATA CONFRONTANDO LA DATA DI INIZIO CON IL CLOCK
+pub fn is_election_started(
+    start_date: &str
+) -> Result<bool, ProgramError> {
+
+    let parsed_start_date = NaiveDateTime::parse_from_str(start_date, "%Y-%m-%d %H:%M:%S").map_err(|_| ProgramError::InvalidAccountData)?;
+    let now = Clock::get()?.unix_timestamp;
+
+    Ok(now >= parsed_start_date.timestamp())
+}
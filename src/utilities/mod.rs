@@ -1 +1,7 @@
-pub mod election_account_utilities;
\ No newline at end of file
+pub mod election_account_utilities;
+pub mod token_account_utilities;
+pub mod account_size_utilities;
+pub mod account_state_utilities;
+pub mod ed25519_utilities;
+#[cfg(test)]
+pub mod test_utilities;
@@ -0,0 +1,127 @@
+use std::{cell::RefCell, sync::Once};
+
+use solana_program::{
+    account_info::AccountInfo,
+    clock::Clock,
+    entrypoint::{ProgramResult, SUCCESS},
+    instruction::Instruction,
+    program_stubs::{set_syscall_stubs, SyscallStubs},
+    pubkey::Pubkey,
+    rent::Rent
+};
+
+use bytemuck::Zeroable;
+
+use crate::{state::{account_header::AccountState, vote_counters_state::VoteCountersHeader}, utilities::account_state_utilities::pack_account};
+
+//ACCOUNT DI PROVA: POSSIEDE LAMPORTS E DATI A CUI PUNTANO GLI AccountInfo DELL'ISTRUZIONE
+pub struct TestAccount {
+    pub key: Pubkey,
+    pub owner: Pubkey,
+    pub lamports: u64,
+    pub data: Vec<u8>,
+    pub is_signer: bool,
+}
+
+impl TestAccount {
+    //ACCOUNT VUOTO DEL SYSTEM PROGRAM, COME UN PDA NON ANCORA CREATO
+    pub fn empty(key: Pubkey) -> Self {
+        TestAccount {
+            key,
+            owner: solana_program::system_program::id(),
+            lamports: 0,
+            data: Vec::new(),
+            is_signer: false,
+        }
+    }
+
+    pub fn signer(key: Pubkey) -> Self {
+        TestAccount {
+            is_signer: true,
+            lamports: 1_000_000_000,
+            ..TestAccount::empty(key)
+        }
+    }
+
+    //ACCOUNT DEL PROGRAMMA CON LO STATO GIA' SALVATO CON LA SUA INTESTAZIONE
+    pub fn with_state<T: AccountState>(program_id: &Pubkey, key: Pubkey, state: &T) -> Self {
+        TestAccount {
+            owner: *program_id,
+            lamports: 1_000_000,
+            data: pack_account(state).unwrap(),
+            ..TestAccount::empty(key)
+        }
+    }
+
+    pub fn account_info(&mut self) -> AccountInfo<'_> {
+        AccountInfo::new(&self.key, self.is_signer, true, &mut self.lamports, &mut self.data, &self.owner, false, 0)
+    }
+}
+
+pub fn account_infos(accounts: &mut [TestAccount]) -> Vec<AccountInfo<'_>> {
+    accounts.iter_mut().map(TestAccount::account_info).collect()
+}
+
+//DATI DI UN ACCOUNT CONTATORI A ZERO CON UNA POSIZIONE PER CANDIDATO
+pub fn vote_counters(number_of_candidates: usize) -> Vec<u8> {
+    fixed_counters(VoteCountersHeader::DISCRIMINATOR, VoteCountersHeader::VERSION, number_of_candidates)
+}
+
+//DATI A ZERO DI UN ACCOUNT CON IL LAYOUT FISSO DEI CONTATORI E L'INTESTAZIONE INDICATA
+pub fn fixed_counters(discriminator: [u8; 8], version: u8, number_of_candidates: usize) -> Vec<u8> {
+    let header = VoteCountersHeader {
+        discriminator,
+        version,
+        ..Zeroable::zeroed()
+    };
+    let mut data = bytemuck::bytes_of(&header).to_vec();
+    data.resize(VoteCountersHeader::space(number_of_candidates), 0);
+    data
+}
+
+//ISTANTE RESTITUITO DAL SYSVAR CLOCK DEI TEST: 2024-01-01 00:00:00
+pub const TEST_UNIX_TIMESTAMP: i64 = 1_704_067_200;
+
+thread_local! {
+    //ISTRUZIONI INVOCATE DAL PROGRAMMA NEL THREAD DEL TEST
+    static INVOKED: RefCell<Vec<Instruction>> = const { RefCell::new(Vec::new()) };
+}
+
+//SYSVAR E CPI FUORI DALLA CHAIN: LE INVOCAZIONI VENGONO SOLO REGISTRATE, NON ESEGUITE
+struct TestSyscallStubs;
+
+impl SyscallStubs for TestSyscallStubs {
+    fn sol_invoke_signed(
+        &self,
+        instruction: &Instruction,
+        _account_infos: &[AccountInfo],
+        _signers_seeds: &[&[&[u8]]],
+    ) -> ProgramResult {
+        INVOKED.with(|invoked| invoked.borrow_mut().push(instruction.clone()));
+        Ok(())
+    }
+
+    fn sol_get_clock_sysvar(&self, var_addr: *mut u8) -> u64 {
+        let clock = Clock { unix_timestamp: TEST_UNIX_TIMESTAMP, ..Clock::default() };
+        unsafe { std::ptr::write(var_addr as *mut Clock, clock) };
+        SUCCESS
+    }
+
+    fn sol_get_rent_sysvar(&self, var_addr: *mut u8) -> u64 {
+        unsafe { std::ptr::write(var_addr as *mut Rent, Rent::default()) };
+        SUCCESS
+    }
+}
+
+//INSTALLA GLI STUB E SVUOTA LE INVOCAZIONI REGISTRATE DAL THREAD
+pub fn setup_syscall_stubs() {
+    static INSTALL: Once = Once::new();
+    INSTALL.call_once(|| {
+        set_syscall_stubs(Box::new(TestSyscallStubs));
+    });
+    INVOKED.with(|invoked| invoked.borrow_mut().clear());
+}
+
+pub fn invoked_instructions() -> Vec<Instruction> {
+    INVOKED.with(|invoked| invoked.borrow().clone())
+}
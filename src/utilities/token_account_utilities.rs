@@ -0,0 +1,79 @@
+use solana_program::{
+    account_info::AccountInfo,
+    entrypoint::ProgramResult,
+    instruction::{AccountMeta, Instruction},
+    msg,
+    program::invoke_signed,
+    program_error::ProgramError,
+    pubkey,
+    pubkey::Pubkey,
+};
+
+//ID DEL PROGRAMMA SPL TOKEN
+pub const TOKEN_PROGRAM_ID: Pubkey = pubkey!("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA");
+
+//DIMENSIONE DI UN ACCOUNT SPL TOKEN
+const TOKEN_ACCOUNT_LEN: usize = 165;
+//OFFSET DELLO STATO (0 = NON INIZIALIZZATO, 1 = INIZIALIZZATO, 2 = CONGELATO)
+const TOKEN_ACCOUNT_STATE_OFFSET: usize = 108;
+//ISTRUZIONE TRANSFER DEL PROGRAMMA SPL TOKEN
+const TOKEN_TRANSFER_INSTRUCTION: u8 = 3;
+
+pub struct TokenAccount {
+    pub mint: Pubkey,
+    pub owner: Pubkey,
+    pub amount: u64,
+}
+
+//LEGGE MINT, PROPRIETARIO E SALDO DI UN ACCOUNT SPL TOKEN
+pub fn unpack_token_account(
+    token_account: &AccountInfo
+) -> Result<TokenAccount, ProgramError> {
+    if *token_account.owner != TOKEN_PROGRAM_ID {
+        msg!("{} is not a token account", token_account.key);
+        return Err(ProgramError::IllegalOwner)
+    }
+
+    let data = token_account.data.borrow();
+    if data.len() != TOKEN_ACCOUNT_LEN || data[TOKEN_ACCOUNT_STATE_OFFSET] != 1 {
+        msg!("Token account {} is not usable", token_account.key);
+        return Err(ProgramError::InvalidAccountData)
+    }
+
+    Ok(TokenAccount {
+        mint: Pubkey::try_from(&data[0..32]).unwrap(),
+        owner: Pubkey::try_from(&data[32..64]).unwrap(),
+        amount: u64::from_le_bytes(data[64..72].try_into().unwrap()),
+    })
+}
+
+//TRASFERISCE TOKEN TRAMITE IL PROGRAMMA SPL TOKEN
+pub fn transfer_tokens<'a>(
+    token_program: &AccountInfo<'a>,
+    source: &AccountInfo<'a>,
+    destination: &AccountInfo<'a>,
+    authority: &AccountInfo<'a>,
+    amount: u64,
+    signer_seeds: &[&[&[u8]]]
+) -> ProgramResult {
+    if *token_program.key != TOKEN_PROGRAM_ID {
+        return Err(ProgramError::IncorrectProgramId)
+    }
+
+    let mut data = vec![TOKEN_TRANSFER_INSTRUCTION];
+    data.extend_from_slice(&amount.to_le_bytes());
+
+    invoke_signed(
+        &Instruction {
+            program_id: TOKEN_PROGRAM_ID,
+            accounts: vec![
+                AccountMeta::new(*source.key, false),
+                AccountMeta::new(*destination.key, false),
+                AccountMeta::new_readonly(*authority.key, true),
+            ],
+            data,
+        },
+        &[source.clone(), destination.clone(), authority.clone(), token_program.clone()],
+        signer_seeds
+    )
+}